@@ -0,0 +1,238 @@
+use std::path::Path;
+use std::time::Duration;
+
+use advent22::{Day, Timings, DAYS};
+
+use crate::json::Value;
+
+/// A median this many times the baseline's counts as a regression.
+const REGRESSION_THRESHOLD: f64 = 1.1;
+
+/// Summary statistics over the samples of one phase.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Stats {
+    min: Duration,
+    median: Duration,
+    p95: Duration,
+}
+
+impl Stats {
+    /// Returns None if there are no samples.
+    fn new(mut samples: Vec<Duration>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort();
+        // Nearest-rank percentiles, so all stats are actual samples.
+        let percentile = |p: usize| samples[(samples.len() * p).div_ceil(100) - 1];
+        Some(Self {
+            min: samples[0],
+            median: percentile(50),
+            p95: percentile(95),
+        })
+    }
+
+    fn to_json(self) -> Value {
+        let ns = |d: Duration| Value::Number(d.as_nanos() as f64);
+        Value::Object(vec![
+            ("min_ns".to_string(), ns(self.min)),
+            ("median_ns".to_string(), ns(self.median)),
+            ("p95_ns".to_string(), ns(self.p95)),
+        ])
+    }
+}
+
+/// The statistics for each phase of a day.
+#[derive(Debug)]
+struct DayStats {
+    day: usize,
+    runs: usize,
+    phases: Vec<(&'static str, Stats)>,
+}
+
+impl DayStats {
+    fn new(day: usize, samples: &[Timings]) -> Self {
+        let phases: [(_, Option<Vec<_>>); 3] = [
+            ("parse", samples.iter().map(|t| Some(t.parse)).collect()),
+            ("part1", samples.iter().map(|t| Some(t.part1)).collect()),
+            ("part2", samples.iter().map(|t| t.part2).collect()),
+        ];
+        Self {
+            day,
+            runs: samples.len(),
+            phases: phases
+                .into_iter()
+                .filter_map(|(phase, samples)| Some((phase, Stats::new(samples?)?)))
+                .collect(),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut entries = vec![
+            ("day".to_string(), self.day.into()),
+            ("runs".to_string(), self.runs.into()),
+        ];
+        for (phase, stats) in &self.phases {
+            entries.push((phase.to_string(), stats.to_json()));
+        }
+        Value::Object(entries)
+    }
+}
+
+/// The median of each day and phase in a previously saved baseline.
+#[derive(Debug, Default)]
+struct Baseline(Vec<(usize, String, Duration)>);
+
+impl Baseline {
+    fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        Self::parse(&json).map_err(|e| format!("invalid baseline {}: {e}", path.display()))
+    }
+
+    fn parse(json: &str) -> Result<Self, String> {
+        let mut rv = vec![];
+        let json = Value::parse(json)?;
+        for entry in json.as_array().ok_or("expected an array of days")? {
+            let day = entry
+                .get("day")
+                .and_then(Value::as_f64)
+                .ok_or("missing day")? as usize;
+            for phase in ["parse", "part1", "part2"] {
+                if let Some(stats) = entry.get(phase) {
+                    let median = stats
+                        .get("median_ns")
+                        .and_then(Value::as_f64)
+                        .ok_or(format!("missing median for day {day} {phase}"))?;
+                    rv.push((day, phase.to_string(), Duration::from_nanos(median as u64)));
+                }
+            }
+        }
+        Ok(Self(rv))
+    }
+
+    fn median(&self, day: usize, phase: &str) -> Option<Duration> {
+        self.0
+            .iter()
+            .find(|(d, p, _)| *d == day && p == phase)
+            .map(|(_, _, median)| *median)
+    }
+}
+
+/// Runs each of the selected days sequentially, timing every phase
+/// over a number of runs, and prints their statistics. Optionally
+/// compares the medians against a baseline, and saves the results as
+/// a new one. Returns true if no day failed and nothing regressed.
+pub fn bench(
+    days: &[usize],
+    input: Option<&str>,
+    runs: usize,
+    baseline: Option<&Path>,
+    save: Option<&Path>,
+) -> bool {
+    let baseline = match baseline.map(Baseline::load).transpose() {
+        Ok(baseline) => baseline.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("{e}");
+            return false;
+        }
+    };
+    let mut ok = true;
+    let mut results = vec![];
+    let mut total = Duration::ZERO;
+
+    println!(
+        "day  phase  {:>10}  {:>10}  {:>10}  vs baseline",
+        "min", "median", "p95"
+    );
+    for &day in days {
+        let Day {
+            time,
+            input: embedded,
+            ..
+        } = DAYS[day - 1];
+        let input = input.unwrap_or(embedded);
+        // The first run warms up caches, and is not counted.
+        let samples: Result<Vec<_>, _> = (0..=runs).map(|_| time(input)).skip(1).collect();
+        let samples = match samples {
+            Ok(samples) => samples,
+            Err(e) => {
+                println!("{day:>3}  error: {e}");
+                ok = false;
+                continue;
+            }
+        };
+        let stats = DayStats::new(day, &samples);
+        for (phase, s) in &stats.phases {
+            let comparison = match baseline.median(day, phase) {
+                Some(before) if !before.is_zero() => {
+                    let ratio = s.median.as_secs_f64() / before.as_secs_f64();
+                    let change = format!("{:+.1}%", (ratio - 1.0) * 100.0);
+                    if ratio > REGRESSION_THRESHOLD {
+                        ok = false;
+                        format!("{change} REGRESSION")
+                    } else {
+                        change
+                    }
+                }
+                _ => String::new(),
+            };
+            let row = format!(
+                "{day:>3}  {phase:<5}  {:>10.1?}  {:>10.1?}  {:>10.1?}  {comparison}",
+                s.min, s.median, s.p95
+            );
+            println!("{}", row.trim_end());
+            total += s.median;
+        }
+        results.push(stats);
+    }
+    println!("total of medians: {total:.1?}");
+
+    if let Some(path) = save {
+        let json = Value::Array(results.iter().map(DayStats::to_json).collect());
+        if let Err(e) = std::fs::write(path, format!("{json:#}\n")) {
+            eprintln!("failed to write {}: {e}", path.display());
+            ok = false;
+        }
+    }
+    ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn percentiles() {
+        let stats = Stats::new((1..=20).rev().map(ms).collect()).unwrap();
+        assert_eq!(
+            (stats.min, stats.median, stats.p95),
+            (ms(1), ms(10), ms(19))
+        );
+        let stats = Stats::new(vec![ms(3)]).unwrap();
+        assert_eq!((stats.min, stats.median, stats.p95), (ms(3), ms(3), ms(3)));
+        assert_eq!(Stats::new(vec![]), None);
+    }
+
+    #[test]
+    fn baseline_round_trip() {
+        let samples: Vec<_> = [1, 3, 2]
+            .map(|n| Timings {
+                parse: ms(n),
+                part1: ms(n * 10),
+                part2: None,
+            })
+            .to_vec();
+        let stats = DayStats::new(25, &samples);
+        let json = Value::Array(vec![stats.to_json()]);
+        let baseline = Baseline::parse(&format!("{json:#}")).unwrap();
+        assert_eq!(baseline.median(25, "parse"), Some(ms(2)));
+        assert_eq!(baseline.median(25, "part1"), Some(ms(20)));
+        assert_eq!(baseline.median(25, "part2"), None);
+        assert!(Baseline::parse("{}").is_err());
+    }
+}
@@ -1,25 +1,47 @@
-pub fn solve() -> String {
-    let input = include_str!("../inputs/01.txt");
-    format!("{}\n{}", part1(input), part2(input))
-}
+use crate::error::parse_lines;
+use crate::{Error, Result, Solution};
 
-fn part1(input: &str) -> u32 {
-    *elf_calories(input).last().unwrap()
-}
+pub const INPUT: &str = include_str!("../inputs/01.txt");
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    /// Calories carried by each elf, in ascending order.
+    type Input = Vec<u32>;
+    type Part1 = u32;
+    type Part2 = u32;
+
+    const DAY: u8 = 1;
 
-fn part2(input: &str) -> u32 {
-    elf_calories(input).iter().rev().take(3).sum()
+    fn parse(input: &str) -> Result<Self::Input> {
+        elf_calories(input)
+    }
+
+    fn part1(elves: &Self::Input) -> Result<u32> {
+        elves.last().copied().ok_or(Error::new("no elves found"))
+    }
+
+    fn part2(elves: &Self::Input) -> Result<u32> {
+        Ok(elves.iter().rev().take(3).sum())
+    }
 }
 
-fn elf_calories(input: &str) -> Vec<u32> {
-    let count_calories = |elf: &str| -> u32 {
-        elf.split_whitespace()
-            .map(|n| n.parse::<u32>().expect("failed to parse calories"))
-            .sum()
+fn elf_calories(input: &str) -> Result<Vec<u32>> {
+    let count_calories = |elf: &str| -> Result<u32> {
+        let calories = parse_lines(elf, |n| {
+            n.parse::<u32>()
+                .map_err(|_| Error::new("failed to parse calories"))
+        })
+        .map_err(|e| e.within(input, elf))?;
+        Ok(calories.iter().sum())
     };
-    let mut elves: Vec<u32> = input.split("\n\n").map(count_calories).collect();
+    let mut elves: Vec<u32> = input
+        .trim_end()
+        .split("\n\n")
+        .map(count_calories)
+        .collect::<Result<_>>()?;
     elves.sort();
-    elves
+    Ok(elves)
 }
 
 #[cfg(test)]
@@ -44,11 +66,11 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 24000);
+        assert_eq!(Day01::part1(&Day01::parse(INPUT).unwrap()), Ok(24000));
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(INPUT), 45000);
+        assert_eq!(Day01::part2(&Day01::parse(INPUT).unwrap()), Ok(45000));
     }
 }
@@ -1,48 +1,79 @@
-pub fn solve() -> String {
-    let input = include_str!("../inputs/02.txt");
-    format!("{}\n{}", part1(input), part2(input))
+use crate::error::parse_lines;
+use crate::{Error, Result, Solution};
+
+pub const INPUT: &str = include_str!("../inputs/02.txt");
+
+pub struct Day02;
+
+impl Solution for Day02 {
+    type Input = Vec<Round>;
+    type Part1 = i32;
+    type Part2 = i32;
+
+    const DAY: u8 = 2;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_lines(input, |l| match l.split_once(' ') {
+            Some((a, _)) if !["A", "B", "C"].contains(&a) => {
+                Err(Error::new("invalid play").located(l, a))
+            }
+            Some((_, b)) if !["X", "Y", "Z"].contains(&b) => {
+                Err(Error::new("invalid strategy").located(l, b))
+            }
+            Some((a, b)) => Ok((a.to_string(), b.to_string())),
+            None => Err(Error::new("invalid round")),
+        })
+    }
+
+    fn part1(rounds: &Self::Input) -> Result<i32> {
+        Ok(calculate(rounds, part1_strategy))
+    }
+
+    fn part2(rounds: &Self::Input) -> Result<i32> {
+        Ok(calculate(rounds, part2_strategy))
+    }
 }
 
+/// A round of rock paper scissors, as the opponent's play (A, B, C),
+/// and the column of the strategy guide (X, Y, Z).
+pub type Round = (String, String);
+
+/// Scores a round, assuming X, Y, Z are the plays to make.
 #[allow(clippy::identity_op)]
-fn part1(input: &str) -> i32 {
-    let strategy = |plays: &str| -> i32 {
-        match plays {
-            "A X" => 1 + 3,
-            "A Y" => 2 + 6,
-            "A Z" => 3 + 0,
-            "B X" => 1 + 0,
-            "B Y" => 2 + 3,
-            "B Z" => 3 + 6,
-            "C X" => 1 + 6,
-            "C Y" => 2 + 0,
-            "C Z" => 3 + 3,
-            _ => panic!("invalid play"),
-        }
-    };
-    calculate(input, strategy)
+fn part1_strategy((opponent, me): &Round) -> i32 {
+    match (opponent.as_str(), me.as_str()) {
+        ("A", "X") => 1 + 3,
+        ("A", "Y") => 2 + 6,
+        ("A", "Z") => 3 + 0,
+        ("B", "X") => 1 + 0,
+        ("B", "Y") => 2 + 3,
+        ("B", "Z") => 3 + 6,
+        ("C", "X") => 1 + 6,
+        ("C", "Y") => 2 + 0,
+        ("C", "Z") => 3 + 3,
+        _ => unreachable!("rounds are validated while parsing"),
+    }
 }
 
+/// Scores a round, assuming X, Y, Z are the outcomes to achieve.
 #[allow(clippy::identity_op)]
-fn part2(input: &str) -> i32 {
-    let strategy = |plays: &str| -> i32 {
-        match plays {
-            "A X" => 3 + 0,
-            "A Y" => 1 + 3,
-            "A Z" => 2 + 6,
-            "B X" => 1 + 0,
-            "B Y" => 2 + 3,
-            "B Z" => 3 + 6,
-            "C X" => 2 + 0,
-            "C Y" => 3 + 3,
-            "C Z" => 1 + 6,
-            _ => panic!("invalid play"),
-        }
-    };
-    calculate(input, strategy)
+fn part2_strategy((opponent, outcome): &Round) -> i32 {
+    match (opponent.as_str(), outcome.as_str()) {
+        ("A", "X") => 3 + 0,
+        ("A", "Y") => 1 + 3,
+        ("A", "Z") => 2 + 6,
+        ("B", "X") => 1 + 0,
+        ("B", "Y") => 2 + 3,
+        ("B", "Z") => 3 + 6,
+        ("C", "X") => 2 + 0,
+        ("C", "Y") => 3 + 3,
+        ("C", "Z") => 1 + 6,
+        _ => unreachable!("rounds are validated while parsing"),
+    }
 }
 
-fn calculate(input: &str, strategy: fn(&str) -> i32) -> i32 {
-    input.lines().map(strategy).sum()
+fn calculate(rounds: &[Round], strategy: fn(&Round) -> i32) -> i32 {
+    rounds.iter().map(strategy).sum()
 }
 
 #[cfg(test)]
@@ -56,11 +87,17 @@ C Z";
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 15);
+        assert_eq!(Day02::part1(&Day02::parse(INPUT).unwrap()), Ok(15));
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(INPUT), 12);
+        assert_eq!(Day02::part2(&Day02::parse(INPUT).unwrap()), Ok(12));
+    }
+
+    #[test]
+    fn invalid_play() {
+        let e = Day02::parse("A Y\nB Q").unwrap_err();
+        assert_eq!(e.to_string(), "line 2, column 3: invalid strategy");
     }
 }
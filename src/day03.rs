@@ -1,49 +1,78 @@
-use fxhash::{FxHashMap, FxHashSet};
-
-pub fn solve() -> String {
-    let input = include_str!("../inputs/03.txt");
-    let rucksacks: Vec<&str> = input.lines().collect();
-    let duplicates: Vec<char> = rucksacks
-        .iter()
-        .map(|&s| -> char {
-            let (a, b) = split_in_half(s);
-            let sa: FxHashSet<char> = a.chars().collect();
-            let sb: FxHashSet<char> = b.chars().collect();
-            let both: Vec<char> = sa.intersection(&sb).copied().collect();
-            both.first().expect("no misplaced item found").to_owned()
+use fxhash::FxHashSet;
+
+use crate::error::parse_lines;
+use crate::{Error, Result, Solution};
+
+pub const INPUT: &str = include_str!("../inputs/03.txt");
+
+pub struct Day03;
+
+impl Solution for Day03 {
+    /// The items in each rucksack.
+    type Input = Vec<String>;
+    type Part1 = u32;
+    type Part2 = u32;
+
+    const DAY: u8 = 3;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_lines(input, |l| {
+            if let Some(idx) = l.find(|c: char| !c.is_ascii_alphabetic()) {
+                Err(Error::new("invalid item").located(l, &l[idx..]))
+            } else {
+                Ok(l.to_string())
+            }
         })
-        .collect();
-    let priorities_map: FxHashMap<char, u32> = ('a'..='z').chain('A'..='Z').zip(1..).collect();
-    let get_priority =
-        |c: &char| -> &u32 { priorities_map.get(c).expect("unable to find priority") };
-
-    let badges: Vec<char> = rucksacks
-        .chunks(3)
-        .map(|chunk| -> char {
-            if let [a, b, c] = chunk {
+    }
+
+    fn part1(rucksacks: &Self::Input) -> Result<u32> {
+        rucksacks
+            .iter()
+            .map(|s| -> Result<char> {
+                let (a, b) = split_in_half(s);
                 let sa: FxHashSet<char> = a.chars().collect();
                 let sb: FxHashSet<char> = b.chars().collect();
-                let sc: FxHashSet<char> = c.chars().collect();
-                sa.intersection(&sb)
-                    .copied()
-                    .collect::<FxHashSet<char>>()
-                    .intersection(&sc)
+                let both: Vec<char> = sa.intersection(&sb).copied().collect();
+                both.first()
                     .copied()
-                    .collect::<Vec<char>>()
-                    .first()
-                    .expect("no common badge item found")
-                    .to_owned()
-            } else {
-                panic!("chunking rucksacks failed");
-            }
-        })
-        .collect();
+                    .ok_or(Error::new(format!("no misplaced item found in {s}")))
+            })
+            .map(|c| c.map(priority))
+            .sum()
+    }
+
+    fn part2(rucksacks: &Self::Input) -> Result<u32> {
+        rucksacks
+            .chunks(3)
+            .map(|chunk| -> Result<char> {
+                if let [a, b, c] = chunk {
+                    let sa: FxHashSet<char> = a.chars().collect();
+                    let sb: FxHashSet<char> = b.chars().collect();
+                    let sc: FxHashSet<char> = c.chars().collect();
+                    sa.intersection(&sb)
+                        .copied()
+                        .collect::<FxHashSet<char>>()
+                        .intersection(&sc)
+                        .copied()
+                        .next()
+                        .ok_or(Error::new("no common badge item found"))
+                } else {
+                    Err(Error::new("incomplete group of elves"))
+                }
+            })
+            .map(|c| c.map(priority))
+            .sum()
+    }
+}
 
-    format!(
-        "{}\n{}",
-        duplicates.iter().map(get_priority).sum::<u32>(),
-        badges.iter().map(get_priority).sum::<u32>()
-    )
+/// Returns the priority of an item, a-z being 1-26, and A-Z being
+/// 27-52.
+fn priority(c: char) -> u32 {
+    match c {
+        'a'..='z' => c as u32 - 'a' as u32 + 1,
+        'A'..='Z' => c as u32 - 'A' as u32 + 27,
+        _ => unreachable!("items are validated while parsing"),
+    }
 }
 
 fn split_in_half(rs: &str) -> (&str, &str) {
@@ -1,56 +1,75 @@
 use std::str::FromStr;
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/04.txt");
-    let pairs: Vec<Pair> = input
-        .lines()
-        .map(|l| l.parse().expect("failed to parse pair"))
-        .collect();
-    format!(
-        "{}\n{}",
-        pairs.iter().filter(|p| fully_contains(p)).count(),
-        pairs.iter().filter(|p| overlaps(p)).count()
-    )
+use crate::error::parse_lines;
+use crate::{Error, Result, Solution};
+
+pub const INPUT: &str = include_str!("../inputs/04.txt");
+
+pub struct Day04;
+
+impl Solution for Day04 {
+    type Input = Vec<Pair>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    const DAY: u8 = 4;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_lines(input, str::parse)
+    }
+
+    fn part1(pairs: &Self::Input) -> Result<usize> {
+        Ok(pairs.iter().filter(|p| fully_contains(p)).count())
+    }
+
+    fn part2(pairs: &Self::Input) -> Result<usize> {
+        Ok(pairs.iter().filter(|p| overlaps(p)).count())
+    }
 }
 
 #[derive(Debug)]
-struct Pair {
-    first: Range,
-    second: Range,
+pub struct Pair {
+    pub first: Range,
+    pub second: Range,
 }
 
 impl FromStr for Pair {
-    type Err = &'static str;
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> Result<Self> {
         if let Some((first, second)) = s.split_once(',') {
             Ok(Self {
-                first: first.parse()?,
-                second: second.parse()?,
+                first: first.parse().map_err(|e: Error| e.within(s, first))?,
+                second: second.parse().map_err(|e: Error| e.within(s, second))?,
             })
         } else {
-            Err("invalid pair")
+            Err(Error::new("invalid pair"))
         }
     }
 }
 
+/// An inclusive range of section IDs.
 #[derive(Debug)]
-struct Range {
-    start: u32,
-    end: u32,
+pub struct Range {
+    pub start: u32,
+    pub end: u32,
 }
 
 impl FromStr for Range {
-    type Err = &'static str;
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> Result<Self> {
         if let Some((start, end)) = s.split_once('-') {
             Ok(Range {
-                start: start.parse().map_err(|_| "invalid range start")?,
-                end: end.parse().map_err(|_| "invalid range end")?,
+                start: start
+                    .parse()
+                    .map_err(|_| Error::new("invalid range start"))?,
+                end: end
+                    .parse()
+                    .map_err(|_| Error::new("invalid range end").located(s, end))?,
             })
         } else {
-            Err("invalid range")
+            Err(Error::new("invalid range"))
         }
     }
 }
@@ -1,66 +1,101 @@
 use std::str::FromStr;
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/05.txt");
-    let stack_tops = |stacks: &Stacks| -> String {
-        stacks
-            .crates
-            .iter()
-            .map(|s| s.last().unwrap_or(&' '))
-            .collect()
-    };
-
-    let (stacks, moves) = input.split_once("\n\n").expect("failed to split input");
-    let mut stacks: Stacks = stacks.parse().expect("failed to parse stacks");
-
-    moves
-        .lines()
-        .map(|m| m.parse::<Move>().expect("failed to parse move"))
-        .try_for_each(|m| execute_move_9000(&mut stacks, &m))
-        .expect("failed to execute moves");
-    let part1 = stack_tops(&stacks);
-
-    let (stacks, moves) = input.split_once("\n\n").expect("failed to split input");
-    let mut stacks: Stacks = stacks.parse().expect("failed to parse stacks");
-
-    moves
-        .lines()
-        .map(|m| m.parse::<Move>().expect("failed to parse move"))
-        .try_for_each(|m| execute_move_9001(&mut stacks, &m))
-        .expect("failed to execute moves");
-    format!("{part1}\n{}", stack_tops(&stacks))
+use crate::error::parse_lines;
+use crate::{Error, Result, Solution};
+
+pub const INPUT: &str = include_str!("../inputs/05.txt");
+
+pub struct Day05;
+
+impl Solution for Day05 {
+    /// The starting stacks, and the moves to execute.
+    type Input = (Stacks, Vec<Move>);
+    type Part1 = String;
+    type Part2 = String;
+
+    const DAY: u8 = 5;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        let (stacks, moves) = input
+            .split_once("\n\n")
+            .ok_or(Error::new("failed to split stacks and moves"))?;
+        let stacks = stacks.parse().map_err(|e: Error| e.within(input, stacks))?;
+        let moves = parse_lines(moves, str::parse).map_err(|e| e.within(input, moves))?;
+        Ok((stacks, moves))
+    }
+
+    fn part1((stacks, moves): &Self::Input) -> Result<String> {
+        rearrange(stacks.clone(), moves, execute_move_9000)
+    }
+
+    fn part2((stacks, moves): &Self::Input) -> Result<String> {
+        rearrange(stacks.clone(), moves, execute_move_9001)
+    }
+}
+
+/// Executes all moves using the given crane model, returning the
+/// crates on top of each stack afterwards.
+fn rearrange(
+    mut stacks: Stacks,
+    moves: &[Move],
+    crane: fn(&mut Stacks, &Move) -> Result<()>,
+) -> Result<String> {
+    for (i, m) in moves.iter().enumerate() {
+        crane(&mut stacks, m)
+            .map_err(|e| Error::new(format!("failed to execute move {}: {}", i + 1, e.message)))?;
+    }
+    Ok(stacks
+        .crates
+        .iter()
+        .map(|s| s.last().unwrap_or(&' '))
+        .collect())
 }
 
-fn execute_move_9000(stacks: &mut Stacks, m: &Move) -> Result<(), &'static str> {
+fn execute_move_9000(stacks: &mut Stacks, m: &Move) -> Result<()> {
     for _ in 0..m.num {
-        let c = stacks.crates[m.from - 1]
+        let c = stacks
+            .stack(m.from)?
             .pop()
-            .ok_or("failed to execute move")?;
-        stacks.crates[m.to - 1].push(c);
+            .ok_or(Error::new("not enough crates"))?;
+        stacks.stack(m.to)?.push(c);
     }
     Ok(())
 }
 
-fn execute_move_9001(stacks: &mut Stacks, m: &Move) -> Result<(), &'static str> {
-    let split_idx = stacks.crates[m.from - 1].len() - m.num;
-    let cs = stacks.crates[m.from - 1].split_off(split_idx);
-    stacks.crates[m.to - 1].extend(cs);
+fn execute_move_9001(stacks: &mut Stacks, m: &Move) -> Result<()> {
+    let from = stacks.stack(m.from)?;
+    let split_idx = from
+        .len()
+        .checked_sub(m.num)
+        .ok_or(Error::new("not enough crates"))?;
+    let cs = from.split_off(split_idx);
+    stacks.stack(m.to)?.extend(cs);
     Ok(())
 }
 
+/// Stacks of crates, bottom to top.
 #[derive(Clone, Debug, Default)]
-struct Stacks {
-    crates: Vec<Vec<char>>,
+pub struct Stacks {
+    pub crates: Vec<Vec<char>>,
+}
+
+impl Stacks {
+    /// Returns the stack with a given 1-indexed number.
+    fn stack(&mut self, n: usize) -> Result<&mut Vec<char>> {
+        n.checked_sub(1)
+            .and_then(|idx| self.crates.get_mut(idx))
+            .ok_or_else(|| Error::new(format!("no stack {n}")))
+    }
 }
 
 impl FromStr for Stacks {
-    type Err = &'static str;
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> Result<Self> {
         let num = s
             .lines()
             .last()
-            .ok_or("failed to get last stacks line")?
+            .ok_or(Error::new("failed to get last stacks line"))?
             .split_whitespace()
             .count();
         let mut stacks = Self {
@@ -69,7 +104,10 @@ impl FromStr for Stacks {
         for line in s.lines().rev().skip(1) {
             for n in 0..num {
                 // crate positions are 1 5 9 13 ...
-                let c = line.chars().nth(1 + n * 4).ok_or("failed to get crate")?;
+                let c = line
+                    .chars()
+                    .nth(1 + n * 4)
+                    .ok_or_else(|| Error::new("failed to get crate").within(s, line))?;
                 if !c.is_whitespace() {
                     stacks.crates[n].push(c);
                 }
@@ -79,33 +117,29 @@ impl FromStr for Stacks {
     }
 }
 
+/// Moves num crates between two stacks, which are 1-indexed.
 #[derive(Debug)]
-struct Move {
-    num: usize,
-    from: usize,
-    to: usize,
+pub struct Move {
+    pub num: usize,
+    pub from: usize,
+    pub to: usize,
 }
 
 impl FromStr for Move {
-    type Err = &'static str;
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> Result<Self> {
         let mut words = s.split_whitespace();
-        let num = words
-            .nth(1)
-            .ok_or("failed to get move num")?
-            .parse()
-            .map_err(|_| "failed to parse move num")?;
-        let from = words
-            .nth(1)
-            .ok_or("failed to get move from")?
-            .parse()
-            .map_err(|_| "failed to parse move from")?;
-        let to = words
-            .nth(1)
-            .ok_or("failed to get move to")?
-            .parse()
-            .map_err(|_| "failed to parse move to")?;
+        let mut number = |what: &str| -> Result<usize> {
+            let word = words
+                .nth(1)
+                .ok_or_else(|| Error::new(format!("failed to get move {what}")))?;
+            word.parse()
+                .map_err(|_| Error::new(format!("failed to parse move {what}")).located(s, word))
+        };
+        let num = number("num")?;
+        let from = number("from")?;
+        let to = number("to")?;
         Ok(Self { num, from, to })
     }
 }
@@ -1,16 +1,34 @@
 use fxhash::FxHashSet;
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/06.txt");
-    format!(
-        "{}\n{}",
-        find_start(input, 4).expect("unable to find start"),
-        find_start(input, 14).expect("unable to find start")
-    )
+use crate::{Error, Result, Solution};
+
+pub const INPUT: &str = include_str!("../inputs/06.txt");
+
+pub struct Day06;
+
+impl Solution for Day06 {
+    /// The datastream buffer.
+    type Input = String;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    const DAY: u8 = 6;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        Ok(input.to_string())
+    }
+
+    fn part1(message: &Self::Input) -> Result<usize> {
+        find_start(message, 4).ok_or(Error::new("unable to find start of packet"))
+    }
+
+    fn part2(message: &Self::Input) -> Result<usize> {
+        find_start(message, 14).ok_or(Error::new("unable to find start of message"))
+    }
 }
 
 fn find_start(message: &str, size: usize) -> Option<usize> {
-    (0..message.len() - size)
+    (0..message.len().saturating_sub(size))
         .find(|&i| {
             message
                 .chars()
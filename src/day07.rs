@@ -1,39 +1,59 @@
-use std::path::{Path, PathBuf};
+use std::cmp::Reverse;
+use std::path::Path;
 use std::str::FromStr;
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/07.txt");
-    let entries: Vec<Entry> = input
-        .split("$ ")
-        .skip(1)
-        .map(|e| e.parse().expect("failed to parse entry"))
-        .collect();
-    let fs_tree = build_fs_tree(&entries[1..]);
-    let mut directories: Vec<&Node> = fs_tree.directories();
-
-    let part1 = directories
-        .iter()
-        .copied()
-        .filter_map(|n| {
-            let s = n.get_size();
-            if s <= 100_000 {
-                Some(s)
-            } else {
-                None
-            }
-        })
-        .sum::<u32>();
+use fxhash::{FxHashMap, FxHashSet};
+
+use crate::{Error, Result, Solution};
+
+pub const INPUT: &str = include_str!("../inputs/07.txt");
+
+pub struct Day07;
+
+impl Solution for Day07 {
+    type Input = FileSystem;
+    type Part1 = u64;
+    type Part2 = u64;
 
-    let required_space = 30_000_000 - (70_000_000 - directories[0].get_size());
-    directories.sort_by_cached_key(|d| d.get_size());
-    let part2 = directories
-        .iter()
-        .find(|d| d.get_size() >= required_space)
-        .expect("no directory is large enough")
-        .get_size();
-    format!("{part1}\n{part2}")
+    const DAY: u8 = 7;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        if input.is_empty() {
+            return build_fs_tree(input, &[]);
+        }
+        // Commands start lines, and the session starts with one.
+        let Some(session) = input.strip_prefix("$ ") else {
+            return Err(Error::new("expected a command").at(1, 1));
+        };
+        let entries: Vec<(&str, Entry)> = session
+            .split("\n$ ")
+            .map(|e| Ok((e, e.parse().map_err(|err: Error| err.within(input, e))?)))
+            .collect::<Result<_>>()?;
+        build_fs_tree(input, &entries)
+    }
+
+    fn part1(fs: &Self::Input) -> Result<u64> {
+        Ok(fs
+            .directories()
+            .map(|d| fs.size(d))
+            .filter(|&s| s <= 100_000)
+            .sum())
+    }
+
+    fn part2(fs: &Self::Input) -> Result<u64> {
+        Ok(fs
+            .deletion_plan(DISK_SIZE, FREE_SPACE_NEEDED)?
+            .iter()
+            .map(|&d| fs.size(d))
+            .sum())
+    }
 }
 
+/// The size of the disk.
+pub const DISK_SIZE: u64 = 70_000_000;
+/// The free space needed to run the update.
+pub const FREE_SPACE_NEEDED: u64 = 30_000_000;
+
 #[derive(Debug)]
 struct Entry {
     command: Command,
@@ -41,11 +61,11 @@ struct Entry {
 }
 
 impl FromStr for Entry {
-    type Err = &'static str;
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> Result<Self> {
         let mut lines = s.lines();
-        let command: Command = lines.next().expect("failed to parse command").parse()?;
+        let command: Command = lines.next().ok_or(Error::new("missing command"))?.parse()?;
         let output: Vec<String> = lines.map(str::to_string).collect();
         Ok(Self { command, output })
     }
@@ -58,9 +78,9 @@ enum Command {
 }
 
 impl FromStr for Command {
-    type Err = &'static str;
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> Result<Self> {
         if s == "ls" {
             return Ok(Self::Ls);
         }
@@ -69,109 +89,862 @@ impl FromStr for Command {
                 path: path.to_string(),
             })
         } else {
-            Err("failed to parse command")
+            Err(Error::new("failed to parse command"))
         }
     }
 }
 
+/// A file system, with all files and directories kept in one list
+/// and referring to each other by index.
 #[derive(Clone, Debug)]
-enum Node {
-    File { name: PathBuf, size: u32 },
-    Dir { name: PathBuf, contents: Vec<Node> },
+pub struct FileSystem {
+    nodes: Vec<Node>,
 }
 
-impl Node {
-    fn get_name(&self) -> &PathBuf {
-        match self {
-            Self::File { name, .. } => name,
-            Self::Dir { name, .. } => name,
-        }
-    }
-
-    /// Given a root node, find a child node by following a path.
-    fn find_node(&mut self, path: &Path) -> Option<&mut Node> {
-        let mut current = self;
-        for comp in path.components().skip(1) {
-            if let Self::Dir { contents, .. } = current {
-                if let Some(node) = contents
-                    .iter_mut()
-                    .find(|node| node.get_name() == comp.as_os_str())
-                {
-                    current = node;
-                } else {
-                    return None;
-                };
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub name: String,
+    /// The directory this is in, None for the root directory.
+    pub parent: Option<usize>,
+    /// The total size, including everything below a directory.
+    pub size: u64,
+    pub kind: Kind,
+}
+
+#[derive(Clone, Debug)]
+pub enum Kind {
+    File,
+    Dir {
+        /// The files and directories in this one, by name.
+        children: FxHashMap<String, usize>,
+        /// Whether the contents have been listed yet.
+        listed: bool,
+    },
+}
+
+impl FileSystem {
+    /// The index of the root directory.
+    pub const ROOT: usize = 0;
+
+    fn new() -> Self {
+        Self {
+            nodes: vec![Node {
+                name: String::new(),
+                parent: None,
+                size: 0,
+                kind: Kind::Dir {
+                    children: FxHashMap::default(),
+                    listed: false,
+                },
+            }],
+        }
+    }
+
+    pub fn node(&self, idx: usize) -> &Node {
+        &self.nodes[idx]
+    }
+
+    /// Returns the total size of a file or directory.
+    pub fn size(&self, idx: usize) -> u64 {
+        self.nodes[idx].size
+    }
+
+    /// Returns a file or directory in a directory by name.
+    pub fn child(&self, dir: usize, name: &str) -> Option<usize> {
+        match &self.nodes[dir].kind {
+            Kind::Dir { children, .. } => children.get(name).copied(),
+            Kind::File => None,
+        }
+    }
+
+    /// Returns all directories, parents before their children.
+    pub fn directories(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.nodes.len()).filter(|&idx| matches!(self.nodes[idx].kind, Kind::Dir { .. }))
+    }
+
+    /// Returns the files and directories in a directory, by name.
+    pub fn children(&self, dir: usize) -> Vec<usize> {
+        let mut children: Vec<usize> = match &self.nodes[dir].kind {
+            Kind::Dir { children, .. } => children.values().copied().collect(),
+            Kind::File => vec![],
+        };
+        children.sort_by(|&a, &b| self.nodes[a].name.cmp(&self.nodes[b].name));
+        children
+    }
+
+    /// Returns whether a file or directory is in a directory, or is
+    /// the directory itself.
+    pub fn contains(&self, dir: usize, mut idx: usize) -> bool {
+        loop {
+            if idx == dir {
+                return true;
+            }
+            match self.nodes[idx].parent {
+                Some(parent) => idx = parent,
+                None => return false,
+            }
+        }
+    }
+
+    /// Returns the n largest directories, largest first.
+    pub fn largest_directories(&self, n: usize) -> Vec<usize> {
+        let mut directories: Vec<usize> = self.directories().collect();
+        directories.sort_by_cached_key(|&d| (Reverse(self.size(d)), self.path(d)));
+        directories.truncate(n);
+        directories
+    }
+
+    /// Returns the fewest directories to delete to have some free
+    /// space on a disk of a given size, freeing as little as possible
+    /// with that many. The root directory can't be deleted.
+    pub fn deletion_plan(&self, disk_size: u64, free_space: u64) -> Result<Vec<usize>> {
+        let needed = free_space.saturating_sub(
+            disk_size
+                .checked_sub(self.size(Self::ROOT))
+                .ok_or(Error::new("file system is larger than the disk"))?,
+        );
+        let most: u64 = self
+            .directories()
+            .filter(|&d| self.nodes[d].parent == Some(Self::ROOT))
+            .map(|d| self.size(d))
+            .sum();
+        if most < needed {
+            return Err(Error::new("deleting directories cannot free enough space"));
+        }
+        let mut candidates: Vec<usize> = self.directories().skip(1).collect();
+        candidates.sort_by_key(|&d| Reverse(self.size(d)));
+        for count in 0..=candidates.len() {
+            let mut best = None;
+            self.plan_deletion(&candidates, count, needed, &mut vec![], 0, &mut best);
+            if let Some((_, mut plan)) = best {
+                plan.sort_by_cached_key(|&d| self.path(d));
+                return Ok(plan);
+            }
+        }
+        unreachable!()
+    }
+
+    /// Tries to pick a number of directories from the candidates,
+    /// none inside another, freeing enough space but less than the
+    /// best plan so far.
+    fn plan_deletion(
+        &self,
+        candidates: &[usize],
+        count: usize,
+        needed: u64,
+        chosen: &mut Vec<usize>,
+        freed: u64,
+        best: &mut Option<(u64, Vec<usize>)>,
+    ) {
+        if chosen.len() == count {
+            if freed >= needed && best.as_ref().is_none_or(|(f, _)| freed < *f) {
+                *best = Some((freed, chosen.clone()));
+            }
+            return;
+        }
+        for (i, &dir) in candidates.iter().enumerate() {
+            // The candidates are sorted by size, so if the largest
+            // ones left can't free enough, none can.
+            let most: u64 = candidates[i..]
+                .iter()
+                .take(count - chosen.len())
+                .map(|&d| self.size(d))
+                .sum();
+            if freed + most < needed {
+                break;
+            }
+            let freed = freed + self.size(dir);
+            if best.as_ref().is_some_and(|(f, _)| freed >= *f)
+                || chosen
+                    .iter()
+                    .any(|&c| self.contains(c, dir) || self.contains(dir, c))
+            {
+                continue;
+            }
+            chosen.push(dir);
+            self.plan_deletion(&candidates[i + 1..], count, needed, chosen, freed, best);
+            chosen.pop();
+        }
+    }
+
+    /// Draws the file system like `tree` does.
+    pub fn tree(&self) -> String {
+        let mut out = String::from("/\n");
+        self.draw(Self::ROOT, "", &mut out);
+        out
+    }
+
+    fn draw(&self, dir: usize, indent: &str, out: &mut String) {
+        let children = self.children(dir);
+        for (i, &child) in children.iter().enumerate() {
+            let last = i == children.len() - 1;
+            let branch = if last { "└── " } else { "├── " };
+            out.push_str(&format!("{indent}{branch}{}\n", self.nodes[child].name));
+            let indent = format!("{indent}{}", if last { "    " } else { "│   " });
+            self.draw(child, &indent, out);
+        }
+    }
+
+    /// Lists the size of every directory like `du -h` does, with the
+    /// contents of a directory before the directory itself.
+    pub fn du(&self) -> String {
+        let mut out = String::new();
+        self.du_below(Self::ROOT, &mut out);
+        out
+    }
+
+    fn du_below(&self, dir: usize, out: &mut String) {
+        for child in self.children(dir) {
+            if matches!(self.nodes[child].kind, Kind::Dir { .. }) {
+                self.du_below(child, out);
+            }
+        }
+        out.push_str(&format!(
+            "{}\t{}\n",
+            human_size(self.size(dir)),
+            self.path(dir)
+        ));
+    }
+
+    /// Returns the absolute path of a file or directory.
+    pub fn path(&self, idx: usize) -> String {
+        match self.nodes[idx].parent {
+            None => "/".to_string(),
+            Some(Self::ROOT) => format!("/{}", self.nodes[idx].name),
+            Some(parent) => format!("{}/{}", self.path(parent), self.nodes[idx].name),
+        }
+    }
+
+    /// Returns the directory `cd` changes to from another. Like in a
+    /// shell, `..` does not go further up than the root.
+    fn cd(&self, pwd: usize, path: &str) -> Result<usize> {
+        let mut dir = if path.starts_with('/') {
+            Self::ROOT
+        } else {
+            pwd
+        };
+        for name in path.split('/').filter(|n| !n.is_empty() && *n != ".") {
+            dir = if name == ".." {
+                self.nodes[dir].parent.unwrap_or(Self::ROOT)
             } else {
-                return None;
+                self.child(dir, name)
+                    .filter(|&c| matches!(self.nodes[c].kind, Kind::Dir { .. }))
+                    .ok_or_else(|| {
+                        Error::new(format!("no such directory: {}", self.join(dir, name)))
+                    })?
+            };
+        }
+        Ok(dir)
+    }
+
+    /// Records what `ls` shows in a directory. Listing a directory
+    /// again has to show the same contents. Errors about an item are
+    /// located at its line, counting the command as line 1.
+    fn ls(&mut self, dir: usize, output: &[String]) -> Result<()> {
+        let Kind::Dir { listed, .. } = self.nodes[dir].kind else {
+            unreachable!()
+        };
+        let mut names = FxHashSet::default();
+        for (line, item) in output.iter().enumerate() {
+            self.list(dir, listed, item, &mut names)
+                .map_err(|e| e.at(line + 2, 1))?;
+        }
+        let Kind::Dir { children, listed } = &mut self.nodes[dir].kind else {
+            unreachable!()
+        };
+        if let Some(name) = children.keys().find(|n| !names.contains(n.as_str())) {
+            let name = name.clone();
+            return Err(Error::new(format!(
+                "conflicting listings for {}",
+                self.join(dir, &name)
+            )));
+        }
+        *listed = true;
+        Ok(())
+    }
+
+    /// Records one item of what `ls` shows in a directory.
+    fn list<'a>(
+        &mut self,
+        dir: usize,
+        listed: bool,
+        item: &'a str,
+        names: &mut FxHashSet<&'a str>,
+    ) -> Result<()> {
+        let (t, name) = item
+            .split_once(' ')
+            .ok_or_else(|| Error::new(format!("invalid fs entry: {item}")))?;
+        let size = match t {
+            "dir" => None,
+            _ => Some(
+                t.parse::<u64>()
+                    .map_err(|_| Error::new(format!("invalid file size: {t}")))?,
+            ),
+        };
+        let conflict = || Error::new(format!("conflicting listings for {}", self.join(dir, name)));
+        if !names.insert(name) {
+            return Err(conflict());
+        }
+        match (self.child(dir, name), size) {
+            (Some(idx), None) if matches!(self.nodes[idx].kind, Kind::Dir { .. }) => (),
+            (Some(idx), Some(size))
+                if matches!(self.nodes[idx].kind, Kind::File) && self.nodes[idx].size == size => {}
+            (Some(_), _) => return Err(conflict()),
+            (None, _) if listed => return Err(conflict()),
+            (None, size) => self.add(dir, name, size),
+        }
+        Ok(())
+    }
+
+    /// Adds a file with a size, or a directory, to a directory.
+    fn add(&mut self, dir: usize, name: &str, size: Option<u64>) {
+        let idx = self.nodes.len();
+        self.nodes.push(Node {
+            name: name.to_string(),
+            parent: Some(dir),
+            size: size.unwrap_or_default(),
+            kind: match size {
+                Some(_) => Kind::File,
+                None => Kind::Dir {
+                    children: FxHashMap::default(),
+                    listed: false,
+                },
+            },
+        });
+        if let Kind::Dir { children, .. } = &mut self.nodes[dir].kind {
+            children.insert(name.to_string(), idx);
+        }
+    }
+
+    /// Adds up the sizes of all directories. Children always come
+    /// after their parents, so going backwards adds up each directory
+    /// before it is added to its parent.
+    fn update_sizes(&mut self) {
+        for idx in (1..self.nodes.len()).rev() {
+            let parent = self.nodes[idx].parent.unwrap();
+            self.nodes[parent].size += self.nodes[idx].size;
+        }
+    }
+
+    /// Returns the path of a name in a directory.
+    fn join(&self, dir: usize, name: &str) -> String {
+        match dir {
+            Self::ROOT => format!("/{name}"),
+            _ => format!("{}/{name}", self.path(dir)),
+        }
+    }
+}
+
+/// Writes a size in bytes with a binary unit, rounding up to two
+/// significant digits for small numbers like `du -h`.
+pub fn human_size(size: u64) -> String {
+    if size < 1024 {
+        return size.to_string();
+    }
+    let mut unit = 1024u128;
+    for suffix in ["K", "M", "G", "T", "P", "E"] {
+        let tenths = (size as u128 * 10).div_ceil(unit);
+        if tenths < 100 {
+            return format!("{}.{}{suffix}", tenths / 10, tenths % 10);
+        }
+        let whole = (size as u128).div_ceil(unit);
+        if whole < 1024 || suffix == "E" {
+            return format!("{whole}{suffix}");
+        }
+        unit *= 1024;
+    }
+    unreachable!()
+}
+
+/// Replays the commands, each with the text of the input it was
+/// parsed from, to locate any errors.
+fn build_fs_tree(input: &str, entries: &[(&str, Entry)]) -> Result<FileSystem> {
+    let mut fs = FileSystem::new();
+    let mut pwd = FileSystem::ROOT;
+    for (text, entry) in entries {
+        let replayed = match &entry.command {
+            Command::Cd { path } => fs.cd(pwd, path).map(|dir| pwd = dir),
+            Command::Ls => fs.ls(pwd, &entry.output),
+        };
+        replayed.map_err(|e| e.within(input, text))?;
+    }
+    fs.update_sizes();
+    Ok(fs)
+}
+
+/// A directory tree to write a terminal session for, read from disk
+/// or made up.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DirTree {
+    File {
+        name: String,
+        size: u64,
+    },
+    Dir {
+        name: String,
+        contents: Vec<DirTree>,
+    },
+}
+
+/// How to write a terminal session.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Transcript {
+    /// Seeds the random choices.
+    pub seed: u64,
+    /// Whether to list and visit directories in a random order.
+    pub shuffle: bool,
+    /// Whether to throw in commands that don't find out anything new,
+    /// like listing a directory again.
+    pub redundant: bool,
+}
+
+impl DirTree {
+    /// Reads a directory tree from disk, skipping anything that is not
+    /// a plain file or directory.
+    pub fn read(path: &Path) -> Result<Self> {
+        let io = |err: std::io::Error| Error::new(format!("{}: {err}", path.display()));
+        let name = match path.file_name() {
+            Some(name) => name
+                .to_str()
+                .filter(|n| !n.contains('\n') && !n.contains("$ "))
+                .ok_or_else(|| Error::new(format!("unsupported file name: {name:?}")))?,
+            None => "",
+        }
+        .to_string();
+        let metadata = std::fs::symlink_metadata(path).map_err(io)?;
+        if metadata.is_file() {
+            return Ok(Self::File {
+                name,
+                size: metadata.len(),
+            });
+        }
+        let mut contents = vec![];
+        for entry in std::fs::read_dir(path).map_err(io)? {
+            let entry = entry.map_err(io)?;
+            let file_type = entry.file_type().map_err(io)?;
+            if file_type.is_file() || file_type.is_dir() {
+                contents.push(Self::read(&entry.path())?);
             }
         }
-        Some(current)
+        Ok(Self::Dir { name, contents })
     }
 
-    fn directories(&self) -> Vec<&Self> {
-        match self {
-            Self::Dir { contents, .. } => {
-                let mut v = vec![self];
-                contents.iter().for_each(|n| v.append(&mut n.directories()));
-                v
+    /// Makes up a directory tree, nested up to a depth.
+    pub fn random(seed: u64, depth: usize) -> Self {
+        Self::random_dir(String::new(), depth, &mut Rng(seed))
+    }
+
+    fn random_dir(name: String, depth: usize, rng: &mut Rng) -> Self {
+        let mut names = FxHashSet::default();
+        let mut contents = vec![];
+        for _ in 0..=rng.below(6) {
+            let mut name: String = (0..=rng.below(6))
+                .map(|_| (b'a' + rng.below(26) as u8) as char)
+                .collect();
+            if rng.below(2) == 0 {
+                name.push_str([".txt", ".dat", ".log"][rng.below(3)]);
+            }
+            if !names.insert(name.clone()) {
+                continue;
             }
-            _ => Vec::default(),
+            contents.push(if depth > 0 && rng.below(2) == 0 {
+                Self::random_dir(name, depth - 1, rng)
+            } else {
+                Self::File {
+                    name,
+                    size: rng.below(300_000) as u64 + 1,
+                }
+            });
+        }
+        Self::Dir { name, contents }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::File { name, .. } | Self::Dir { name, .. } => name,
         }
     }
 
-    fn get_size(&self) -> u32 {
+    /// Returns the total size, including everything below a directory.
+    pub fn size(&self) -> u64 {
         match self {
             Self::File { size, .. } => *size,
-            Self::Dir { contents, .. } => contents.iter().map(Self::get_size).sum(),
+            Self::Dir { contents, .. } => contents.iter().map(Self::size).sum(),
         }
     }
-}
 
-fn build_fs_tree(entries: &[Entry]) -> Node {
-    let mut pwd = PathBuf::from("/");
-    let mut root = Node::Dir {
-        name: PathBuf::from("/"),
-        contents: vec![],
-    };
+    /// Writes a terminal session that finds out about every file and
+    /// directory in this one, starting with `cd /`.
+    pub fn transcript(&self, options: &Transcript) -> String {
+        let mut out = String::from("$ cd /\n");
+        self.explore(&mut vec![], options, &mut Rng(options.seed), &mut out);
+        out
+    }
 
-    for entry in entries {
-        match &entry.command {
-            Command::Cd { path } => {
-                pwd = if path == ".." {
-                    pwd.parent().expect("couldn't cd ..").to_path_buf()
-                } else {
-                    pwd.join(path)
+    fn explore<'a>(
+        &'a self,
+        path: &mut Vec<&'a str>,
+        options: &Transcript,
+        rng: &mut Rng,
+        out: &mut String,
+    ) {
+        let Self::Dir { contents, .. } = self else {
+            return;
+        };
+        let mut contents: Vec<&Self> = contents.iter().collect();
+        let mut ls = |rng: &mut Rng, out: &mut String| {
+            if options.shuffle {
+                rng.shuffle(&mut contents);
+            }
+            out.push_str("$ ls\n");
+            for entry in &contents {
+                match entry {
+                    Self::File { name, size } => out.push_str(&format!("{size} {name}\n")),
+                    Self::Dir { name, .. } => out.push_str(&format!("dir {name}\n")),
                 }
             }
-            Command::Ls => {
-                for item in &entry.output {
-                    if let Some((t, name)) = item.split_once(' ') {
-                        let node = if t == "dir" {
-                            Node::Dir {
-                                name: name.into(),
-                                contents: vec![],
-                            }
-                        } else {
-                            Node::File {
-                                name: name.into(),
-                                size: t.parse::<u32>().expect("invalid file size"),
-                            }
-                        };
-                        if let Node::Dir {
-                            ref mut contents, ..
-                        } = root
-                            .find_node(&pwd)
-                            .expect("got lost trying to insert node")
-                        {
-                            contents.push(node);
-                        }
-                    } else {
-                        panic!("invalid fs entry")
-                    }
+        };
+        ls(rng, out);
+        if options.redundant && rng.below(4) == 0 {
+            ls(rng, out);
+        }
+
+        let mut dirs: Vec<&Self> = self.dirs().collect();
+        if options.shuffle {
+            rng.shuffle(&mut dirs);
+        }
+        for dir in dirs {
+            out.push_str(&format!("$ cd {}\n", dir.name()));
+            if options.redundant && rng.below(4) == 0 {
+                out.push_str(&format!("$ cd ..\n$ cd {}\n", dir.name()));
+            }
+            path.push(dir.name());
+            dir.explore(path, options, rng, out);
+            path.pop();
+            out.push_str("$ cd ..\n");
+            if options.redundant && rng.below(4) == 0 {
+                // Go back the long way round, or try to go up from the
+                // root.
+                out.push_str("$ cd /\n");
+                path.iter()
+                    .for_each(|name| out.push_str(&format!("$ cd {name}\n")));
+                if path.is_empty() {
+                    out.push_str("$ cd ..\n");
                 }
             }
         }
     }
 
-    root
+    fn dirs(&self) -> impl Iterator<Item = &Self> {
+        let contents = match self {
+            Self::File { .. } => &[][..],
+            Self::Dir { contents, .. } => contents,
+        };
+        contents.iter().filter(|e| matches!(e, Self::Dir { .. }))
+    }
+}
+
+/// A small pseudo-random number generator (SplitMix64), so that
+/// made-up trees and sessions can be reproduced from a seed.
+struct Rng(u64);
+
+impl Rng {
+    /// Returns a number below n.
+    fn below(&mut self, n: usize) -> usize {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        ((z ^ (z >> 31)) % n as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "\
+$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k";
+
+    #[test]
+    fn part1_example() {
+        assert_eq!(Day07::part1(&Day07::parse(INPUT).unwrap()), Ok(95437));
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(Day07::part2(&Day07::parse(INPUT).unwrap()), Ok(24933642));
+    }
+
+    #[test]
+    fn file_system() {
+        let fs = Day07::parse(INPUT).unwrap();
+        let e = fs.cd(FileSystem::ROOT, "a/e").unwrap();
+        assert_eq!(fs.path(e), "/a/e");
+        assert_eq!(fs.size(e), 584);
+        assert_eq!(fs.size(fs.child(FileSystem::ROOT, "a").unwrap()), 94853);
+        assert_eq!(fs.size(FileSystem::ROOT), 48381165);
+        assert_eq!(fs.cd(e, "../../d/"), fs.cd(FileSystem::ROOT, "/d"));
+        assert_eq!(fs.cd(e, "/../.."), Ok(FileSystem::ROOT));
+        assert_eq!(fs.cd(e, "../f"), Err(Error::new("no such directory: /a/f")));
+    }
+
+    #[test]
+    fn reports() {
+        let fs = Day07::parse(INPUT).unwrap();
+        assert_eq!(
+            fs.tree(),
+            "\
+/
+├── a
+│   ├── e
+│   │   └── i
+│   ├── f
+│   ├── g
+│   └── h.lst
+├── b.txt
+├── c.dat
+└── d
+    ├── d.ext
+    ├── d.log
+    ├── j
+    └── k
+"
+        );
+        assert_eq!(fs.du(), "584\t/a/e\n93K\t/a\n24M\t/d\n47M\t/\n");
+        let largest: Vec<_> = fs
+            .largest_directories(3)
+            .into_iter()
+            .map(|d| fs.path(d))
+            .collect();
+        assert_eq!(largest, ["/", "/d", "/a"]);
+        assert_eq!(fs.largest_directories(10).len(), 4);
+    }
+
+    #[test]
+    fn human_sizes() {
+        let sizes = [
+            0,
+            1023,
+            1024,
+            1536,
+            1537,
+            10239,
+            10240,
+            1048575,
+            1048576,
+            u64::MAX,
+        ];
+        let human: Vec<_> = sizes.into_iter().map(human_size).collect();
+        assert_eq!(
+            human,
+            ["0", "1023", "1.0K", "1.5K", "1.6K", "10K", "10K", "1.0M", "1.0M", "16E"]
+        );
+    }
+
+    #[test]
+    fn deletion_plans() {
+        let fs = Day07::parse(INPUT).unwrap();
+        let plan = |disk_size, free_space| {
+            fs.deletion_plan(disk_size, free_space)
+                .map(|plan| plan.into_iter().map(|d| fs.path(d)).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            plan(DISK_SIZE, FREE_SPACE_NEEDED),
+            Ok(vec!["/d".to_string()])
+        );
+        assert_eq!(plan(DISK_SIZE, 1000), Ok(vec![]));
+        assert_eq!(plan(DISK_SIZE, 21_619_000), Ok(vec!["/a/e".to_string()]));
+        // With a full disk no single directory is large enough. Two
+        // small ones beat one larger one.
+        let used = fs.size(FileSystem::ROOT);
+        assert_eq!(
+            plan(used, 25_000_000),
+            Ok(vec!["/a".to_string(), "/d".to_string()])
+        );
+        assert_eq!(
+            plan(used, 24_933_700),
+            Ok(vec!["/a/e".to_string(), "/d".to_string()])
+        );
+        assert_eq!(
+            plan(used, 30_000_000),
+            Err(Error::new("deleting directories cannot free enough space"))
+        );
+        assert_eq!(
+            plan(1000, 0),
+            Err(Error::new("file system is larger than the disk"))
+        );
+    }
+
+    /// Checks that a file system has the same files and directories
+    /// as the tree it was written down from.
+    fn check_round_trip(fs: &FileSystem, idx: usize, tree: &DirTree) {
+        assert_eq!(fs.size(idx), tree.size(), "{}", fs.path(idx));
+        if let DirTree::Dir { contents, .. } = tree {
+            assert_eq!(fs.children(idx).len(), contents.len(), "{}", fs.path(idx));
+            for entry in contents {
+                let child = fs.child(idx, entry.name()).unwrap();
+                assert_eq!(
+                    matches!(fs.node(child).kind, Kind::Dir { .. }),
+                    matches!(entry, DirTree::Dir { .. })
+                );
+                check_round_trip(fs, child, entry);
+            }
+        }
+    }
+
+    #[test]
+    fn transcripts() {
+        let file = |name: &str, size| DirTree::File {
+            name: name.to_string(),
+            size,
+        };
+        let dir = |name: &str, contents| DirTree::Dir {
+            name: name.to_string(),
+            contents,
+        };
+        let example = dir(
+            "",
+            vec![
+                dir(
+                    "a",
+                    vec![
+                        dir("e", vec![file("i", 584)]),
+                        file("f", 29116),
+                        file("g", 2557),
+                        file("h.lst", 62596),
+                    ],
+                ),
+                file("b.txt", 14848514),
+                file("c.dat", 8504156),
+                dir(
+                    "d",
+                    vec![
+                        file("j", 4060174),
+                        file("d.log", 8033020),
+                        file("d.ext", 5626152),
+                        file("k", 7214296),
+                    ],
+                ),
+            ],
+        );
+        assert_eq!(
+            example.transcript(&Transcript::default()),
+            format!("{INPUT}\n$ cd ..\n")
+        );
+
+        for seed in 0..20 {
+            let tree = DirTree::random(seed, 4);
+            for (shuffle, redundant) in [(false, false), (true, false), (true, true)] {
+                let options = Transcript {
+                    seed,
+                    shuffle,
+                    redundant,
+                };
+                let fs = Day07::parse(&tree.transcript(&options)).unwrap();
+                check_round_trip(&fs, FileSystem::ROOT, &tree);
+            }
+        }
+    }
+
+    #[test]
+    fn transcript_of_directory() {
+        let tree = DirTree::read(&Path::new(env!("CARGO_MANIFEST_DIR")).join("src")).unwrap();
+        let options = Transcript {
+            seed: 7,
+            shuffle: true,
+            redundant: true,
+        };
+        let fs = Day07::parse(&tree.transcript(&options)).unwrap();
+        check_round_trip(&fs, FileSystem::ROOT, &tree);
+        assert!(fs.child(FileSystem::ROOT, "day07.rs").is_some());
+    }
+
+    #[test]
+    fn shell_replay() {
+        let size = |input| Day07::parse(input).map(|fs| fs.size(FileSystem::ROOT));
+        // Going up from the root stays there, and listing again is
+        // fine as long as nothing changed.
+        assert_eq!(
+            size(
+                "$ cd ..\n$ ls\ndir a\n$ cd a\n$ ls\n1 b\n$ cd /\n$ ls\ndir a\n\
+                  $ cd a\n$ ls\n1 b"
+            ),
+            Ok(1)
+        );
+        // Errors are located at the command or line of output that
+        // doesn't fit with what came before.
+        let error = |input| Day07::parse(input).unwrap_err().to_string();
+        assert_eq!(
+            error("$ ls\ndir a\n1 a"),
+            "line 3, column 1: conflicting listings for /a"
+        );
+        assert_eq!(
+            error("$ ls\ndir a\n$ cd a\n$ ls\n1 b\n$ cd ..\n$ cd a\n$ ls\n2 b"),
+            "line 9, column 1: conflicting listings for /a/b"
+        );
+        assert_eq!(
+            error("$ ls\n1 b\n2 c\n$ ls\n2 c"),
+            "line 4, column 3: conflicting listings for /b"
+        );
+        assert_eq!(
+            error("$ ls\n1 b\n$ ls\n1 b\n2 c"),
+            "line 5, column 1: conflicting listings for /c"
+        );
+        assert_eq!(
+            error("$ ls\n1 b\n$ cd b"),
+            "line 3, column 3: no such directory: /b"
+        );
+        assert_eq!(
+            error("$ cd /\n$ ls\ndir a\n$ cd a\n$ cd ../b\n$ ls"),
+            "line 5, column 3: no such directory: /b"
+        );
+        assert_eq!(
+            error("$ ls\ndir a\nb"),
+            "line 3, column 1: invalid fs entry: b"
+        );
+        assert_eq!(size(""), Ok(0));
+        assert_eq!(
+            size("garbage").unwrap_err().to_string(),
+            "line 1, column 1: expected a command"
+        );
+        assert_eq!(
+            size("1 a\n$ ls\n1 b").unwrap_err().to_string(),
+            "line 1, column 1: expected a command"
+        );
+        assert_eq!(
+            size("$ ls\n1 a\n$ rm a").unwrap_err().to_string(),
+            "line 3, column 3: failed to parse command"
+        );
+        // Only a dollar sign at the start of a line marks a command.
+        assert_eq!(size("$ ls\n1 a$ b"), Ok(1));
+    }
 }
@@ -1,45 +1,65 @@
 use std::str::FromStr;
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/08.txt");
-    format!("{}\n{}", part1(input), part2(input))
-}
+use crate::error::parse_lines;
+use crate::{Error, Result, Solution};
 
-fn part1(input: &str) -> usize {
-    let trees: Map = input.parse().expect("invalid map");
-    (0..trees.len()).filter(|&idx| trees.visible(idx)).count()
-}
+pub const INPUT: &str = include_str!("../inputs/08.txt");
+
+pub struct Day08;
+
+impl Solution for Day08 {
+    type Input = Map;
+    type Part1 = usize;
+    type Part2 = u32;
+
+    const DAY: u8 = 8;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        input.parse()
+    }
 
-fn part2(input: &str) -> u32 {
-    let trees: Map = input.parse().expect("invalid map");
-    (0..trees.len())
-        .map(|idx| trees.scenic_score(idx))
-        .max()
-        .unwrap()
+    fn part1(trees: &Self::Input) -> Result<usize> {
+        Ok((0..trees.len()).filter(|&idx| trees.visible(idx)).count())
+    }
+
+    fn part2(trees: &Self::Input) -> Result<u32> {
+        (0..trees.len())
+            .map(|idx| trees.scenic_score(idx))
+            .max()
+            .ok_or(Error::new("empty map"))
+    }
 }
 
+/// A square map of tree heights.
 #[derive(Debug)]
-struct Map {
+pub struct Map {
     size: usize,
     inner: Vec<u8>,
 }
 
 impl FromStr for Map {
-    type Err = &'static str;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut size = 0;
-        let inner: Vec<u8> = s
-            .lines()
-            .flat_map(|l| -> Vec<u8> {
-                size += 1;
-                l.chars()
-                    .map(|c| c.to_digit(10).expect("invalid tree") as u8)
-                    .collect()
-            })
-            .collect();
-
-        Ok(Self { inner, size })
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let rows: Vec<Vec<u8>> = parse_lines(s, |l| {
+            l.chars()
+                .enumerate()
+                .map(|(i, c)| {
+                    c.to_digit(10)
+                        .map(|d| d as u8)
+                        .ok_or_else(|| Error::new("invalid tree").at(1, i + 1))
+                })
+                .collect()
+        })?;
+        let size = rows.len();
+        if let Some(y) = rows.iter().position(|r| r.len() != size) {
+            return Err(Error::new("map is not square").at(y + 1, 1));
+        }
+
+        Ok(Self {
+            inner: rows.concat(),
+            size,
+        })
     }
 }
 
@@ -60,7 +80,7 @@ impl Map {
     }
 
     /// Returns true if the tree at idx is visible from the outside.
-    fn visible(&self, idx: usize) -> bool {
+    pub fn visible(&self, idx: usize) -> bool {
         let (x, y) = self.coords(idx);
         // On the edge?
         if x == 0 || y == 0 || x == self.size - 1 || y == self.size - 1 {
@@ -107,7 +127,7 @@ impl Map {
     }
 
     /// For a given index, returns that tree's scenic score.
-    fn scenic_score(&self, idx: usize) -> u32 {
+    pub fn scenic_score(&self, idx: usize) -> u32 {
         let (x, y) = self.coords(idx);
         let this_tree = self.inner[idx];
 
@@ -148,11 +168,11 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 21);
+        assert_eq!(Day08::part1(&Day08::parse(INPUT).unwrap()), Ok(21));
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(INPUT), 8);
+        assert_eq!(Day08::part2(&Day08::parse(INPUT).unwrap()), Ok(8));
     }
 }
@@ -1,30 +1,39 @@
 use fxhash::FxHashSet;
 use std::str::FromStr;
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/09.txt");
-    format!("{}\n{}", part1(input), part2(input))
-}
+use crate::error::parse_lines;
+use crate::{Error, Result, Solution};
 
-fn part1(input: &str) -> usize {
-    let mut rope = Rope::new(2);
-    input
-        .lines()
-        .map(|l| l.parse().expect("failed to parse move"))
-        .for_each(|m| rope.move_head(&m));
-    rope.tail_history.len()
-}
+pub const INPUT: &str = include_str!("../inputs/09.txt");
+
+pub struct Day09;
 
-fn part2(input: &str) -> usize {
-    let mut rope = Rope::new(10);
-    input
-        .lines()
-        .map(|l| l.parse().expect("failed to parse move"))
-        .for_each(|m| rope.move_head(&m));
-    rope.tail_history.len()
+impl Solution for Day09 {
+    type Input = Vec<Move>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    const DAY: u8 = 9;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_lines(input, str::parse)
+    }
+
+    fn part1(moves: &Self::Input) -> Result<usize> {
+        let mut rope = Rope::new(2);
+        moves.iter().for_each(|m| rope.move_head(m));
+        Ok(rope.tail_history.len())
+    }
+
+    fn part2(moves: &Self::Input) -> Result<usize> {
+        let mut rope = Rope::new(10);
+        moves.iter().for_each(|m| rope.move_head(m));
+        Ok(rope.tail_history.len())
+    }
 }
 
-enum Move {
+/// Moves the head of the rope a number of steps in a direction.
+pub enum Move {
     Up(u8),
     Down(u8),
     Left(u8),
@@ -32,15 +41,19 @@ enum Move {
 }
 
 impl FromStr for Move {
-    type Err = &'static str;
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> Result<Self> {
+        let distance = |n: &str| -> Result<u8> {
+            n.parse()
+                .map_err(|_| Error::new("invalid move distance").located(s, n))
+        };
         match s.split_once(' ') {
-            Some(("U", n)) => Ok(Self::Up(n.parse().map_err(|_| "invalid move distance")?)),
-            Some(("D", n)) => Ok(Self::Down(n.parse().map_err(|_| "invalid move distance")?)),
-            Some(("L", n)) => Ok(Self::Left(n.parse().map_err(|_| "invalid move distance")?)),
-            Some(("R", n)) => Ok(Self::Right(n.parse().map_err(|_| "invalid move distance")?)),
-            _ => Err("invalid move"),
+            Some(("U", n)) => Ok(Self::Up(distance(n)?)),
+            Some(("D", n)) => Ok(Self::Down(distance(n)?)),
+            Some(("L", n)) => Ok(Self::Left(distance(n)?)),
+            Some(("R", n)) => Ok(Self::Right(distance(n)?)),
+            _ => Err(Error::new("invalid move")),
         }
     }
 }
@@ -67,7 +80,7 @@ impl Rope {
         match m {
             Move::Up(n) => {
                 for _ in 0..*n {
-                    let mut head = self.knots.get_mut(0).unwrap();
+                    let head = self.knots.get_mut(0).unwrap();
                     head.1 += 1;
                     for idx in 0..self.knots.len() - 1 {
                         let head = *self.knots.get(idx).unwrap();
@@ -79,7 +92,7 @@ impl Rope {
             }
             Move::Down(n) => {
                 for _ in 0..*n {
-                    let mut head = self.knots.get_mut(0).unwrap();
+                    let head = self.knots.get_mut(0).unwrap();
                     head.1 -= 1;
                     for idx in 0..self.knots.len() - 1 {
                         let head = *self.knots.get(idx).unwrap();
@@ -91,7 +104,7 @@ impl Rope {
             }
             Move::Left(n) => {
                 for _ in 0..*n {
-                    let mut head = self.knots.get_mut(0).unwrap();
+                    let head = self.knots.get_mut(0).unwrap();
                     head.0 -= 1;
                     for idx in 0..self.knots.len() - 1 {
                         let head = *self.knots.get(idx).unwrap();
@@ -103,7 +116,7 @@ impl Rope {
             }
             Move::Right(n) => {
                 for _ in 0..*n {
-                    let mut head = self.knots.get_mut(0).unwrap();
+                    let head = self.knots.get_mut(0).unwrap();
                     head.0 += 1;
                     for idx in 0..self.knots.len() - 1 {
                         let head = *self.knots.get(idx).unwrap();
@@ -163,7 +176,7 @@ R 4
 D 1
 L 5
 R 2";
-        assert_eq!(part1(input), 13)
+        assert_eq!(Day09::part1(&Day09::parse(input).unwrap()), Ok(13))
     }
 
     #[test]
@@ -177,6 +190,6 @@ R 17
 D 10
 L 25
 U 20";
-        assert_eq!(part2(input), 36)
+        assert_eq!(Day09::part2(&Day09::parse(input).unwrap()), Ok(36))
     }
 }
@@ -1,21 +1,42 @@
 use std::str::FromStr;
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/10.txt");
-    format!("{}\n{}", part1(input), part2(input))
-}
+use crate::error::parse_lines;
+use crate::{Error, Result, Solution};
 
-fn part1(input: &str) -> i16 {
-    parse_and_run(input)
-        .enumerate()
-        .skip(19)
-        .step_by(40)
-        .map(|(i, x)| (i as i16 + 1) * x)
-        .sum()
+pub const INPUT: &str = include_str!("../inputs/10.txt");
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    type Input = Vec<Instruction>;
+    type Part1 = i16;
+    /// The capital letters shown on the CRT screen.
+    type Part2 = String;
+
+    const DAY: u8 = 10;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_lines(input, str::parse)
+    }
+
+    fn part1(program: &Self::Input) -> Result<i16> {
+        Ok(run(program)
+            .enumerate()
+            .skip(19)
+            .step_by(40)
+            .map(|(i, x)| (i as i16 + 1) * x)
+            .sum())
+    }
+
+    fn part2(program: &Self::Input) -> Result<String> {
+        read_screen(&render(program))
+    }
 }
 
-fn part2(input: &str) -> String {
-    parse_and_run(input)
+/// Runs the program, and returns the CRT screen it draws as rows of
+/// lit `#` and dark `.` pixels.
+pub fn render(program: &[Instruction]) -> String {
+    run(program)
         .enumerate()
         .map(|(beam, x)| {
             format!(
@@ -31,32 +52,80 @@ fn part2(input: &str) -> String {
         .collect()
 }
 
-/// Parses and runs instructions in input, returning an iterator of X
-/// register values for each CPU cycle.
-fn parse_and_run(input: &str) -> impl Iterator<Item = i16> + '_ {
+/// The letters of the CRT font, each 4 pixels wide and 6 high.
+const GLYPHS: [(char, [&str; 6]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...", "#...", ".#.#", "..#.", "..#.", "..#."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// Reads the letters off a rendered screen. Letters are 4 pixels
+/// wide, followed by a blank column.
+pub fn read_screen(screen: &str) -> Result<String> {
+    let rows: Vec<&str> = screen.lines().collect();
+    let width = rows.first().map_or(0, |r| r.len());
+    if rows.len() != 6 || rows.iter().any(|r| r.len() != width || !r.is_ascii()) {
+        return Err(Error::new("screen is not 6 rows of equal width"));
+    }
+    let mut letters = String::new();
+    let mut unrecognised = vec![];
+    for (i, x) in (0..width.saturating_sub(3)).step_by(5).enumerate() {
+        let glyph: Vec<&str> = rows.iter().map(|r| &r[x..x + 4]).collect();
+        match GLYPHS.iter().find(|(_, g)| *g == *glyph) {
+            Some((letter, _)) => letters.push(*letter),
+            None => unrecognised.push((i + 1).to_string()),
+        }
+    }
+    if !unrecognised.is_empty() {
+        return Err(Error::new(format!(
+            "unrecognised glyphs at positions {}",
+            unrecognised.join(", ")
+        )));
+    }
+    Ok(letters)
+}
+
+/// Runs the instructions, returning an iterator of X register values
+/// for each CPU cycle.
+fn run(program: &[Instruction]) -> impl Iterator<Item = i16> + '_ {
     let mut cpu = Cpu::default();
-    input
-        .lines()
-        .map(|l| l.parse::<Instruction>().expect("invalid instruction"))
-        .flat_map(move |i| cpu.run(&i))
+    program.iter().flat_map(move |i| cpu.run(i))
 }
 
-enum Instruction {
+pub enum Instruction {
     Noop,
     AddX(i16),
 }
 
 impl FromStr for Instruction {
-    type Err = &'static str;
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> Result<Self> {
         if s == "noop" {
             return Ok(Self::Noop);
         }
         if let Some(("addx", n)) = s.split_once(' ') {
-            return Ok(Self::AddX(n.parse().map_err(|_| "invalid addx value")?));
+            return Ok(Self::AddX(
+                n.parse()
+                    .map_err(|_| Error::new("invalid addx value").located(s, n))?,
+            ));
         }
-        Err("invalid instruction")
+        Err(Error::new("invalid instruction"))
     }
 }
 
@@ -240,11 +309,11 @@ noop";
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 13140);
+        assert_eq!(Day10::part1(&Day10::parse(INPUT).unwrap()), Ok(13140));
     }
 
     #[test]
-    fn part2_example() {
+    fn render_example() {
         let expected = "\
 ##..##..##..##..##..##..##..##..##..##..
 ###...###...###...###...###...###...###.
@@ -253,6 +322,41 @@ noop";
 ######......######......######......####
 #######.......#######.......#######.....
 ";
-        assert_eq!(part2(INPUT), expected);
+        assert_eq!(render(&Day10::parse(INPUT).unwrap()), expected);
+    }
+
+    #[test]
+    fn read_letters() {
+        let screen = "\
+###..####.#..#.###..###..#....#..#.###..
+#..#.#....#..#.#..#.#..#.#....#..#.#..#.
+#..#.###..####.#..#.#..#.#....#..#.###..
+###..#....#..#.###..###..#....#..#.#..#.
+#.#..#....#..#.#....#.#..#....#..#.#..#.
+#..#.####.#..#.#....#..#.####..##..###..
+";
+        assert_eq!(read_screen(screen), Ok("REHPRLUB".to_string()));
+    }
+
+    #[test]
+    fn unrecognised_glyphs() {
+        let screen = "\
+.##..####
+#..#.#...
+#..#.###.
+####.#...
+#..#.#...
+#..#.#..#
+";
+        assert_eq!(
+            read_screen(screen),
+            Err(Error::new("unrecognised glyphs at positions 2"))
+        );
+        assert_eq!(
+            Day10::part2(&Day10::parse(INPUT).unwrap()),
+            Err(Error::new(
+                "unrecognised glyphs at positions 1, 2, 3, 4, 5, 6, 7, 8"
+            ))
+        );
     }
 }
@@ -1,31 +1,65 @@
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{digit1, u64, u8},
+    character::complete::{u64, u8},
+    combinator::{map, value},
     multi::separated_list1,
     IResult,
 };
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/11.txt");
-    format!("{}\n{}", part1(input), part2(input))
-}
+use crate::error::nom;
+use crate::{Error, Result, Solution};
+
+pub const INPUT: &str = include_str!("../inputs/11.txt");
+
+pub struct Day11;
+
+impl Solution for Day11 {
+    type Input = Vec<Monkey>;
+    type Part1 = u64;
+    type Part2 = u64;
+
+    const DAY: u8 = 11;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        let mut monkeys: Vec<Monkey> = input
+            .trim_end()
+            .split("\n\n")
+            .map(|s| nom(parse_monkey, "monkey")(s).map_err(|e| e.within(input, s)))
+            .collect::<Result<_>>()?;
+        for (i, m) in monkeys.iter().enumerate() {
+            if m.test == 0 {
+                return Err(Error::new(format!("monkey {i} tests divisibility by 0")));
+            }
+            if let Some(dest) = [m.if_true, m.if_false]
+                .into_iter()
+                .find(|&dest| dest >= monkeys.len())
+            {
+                return Err(Error::new(format!(
+                    "monkey {i} throws to invalid monkey {dest}"
+                )));
+            }
+        }
+        let modulo = monkeys.iter().map(|m| m.test).product();
+        monkeys.iter_mut().for_each(|m| m.modulo = modulo);
+        Ok(monkeys)
+    }
 
-fn part1(input: &str) -> u64 {
-    let mut monkeys = parse_monkeys(input, false);
-    (0..20).for_each(|_| round(&mut monkeys));
-    monkeys.sort_by_key(|m| m.inspections);
-    monkeys
-        .iter()
-        .rev()
-        .take(2)
-        .map(|m| m.inspections)
-        .product()
+    fn part1(monkeys: &Self::Input) -> Result<u64> {
+        Ok(monkey_business(monkeys.clone(), 20))
+    }
+
+    fn part2(monkeys: &Self::Input) -> Result<u64> {
+        let mut monkeys = monkeys.clone();
+        monkeys.iter_mut().for_each(|m| m.ridiculous = true);
+        Ok(monkey_business(monkeys, 10_000))
+    }
 }
 
-fn part2(input: &str) -> u64 {
-    let mut monkeys = parse_monkeys(input, true);
-    (0..10_000).for_each(|_| round(&mut monkeys));
+/// Plays a number of rounds, and returns the product of the two
+/// highest inspection counts.
+fn monkey_business(mut monkeys: Vec<Monkey>, rounds: usize) -> u64 {
+    (0..rounds).for_each(|_| round(&mut monkeys));
     monkeys.sort_by_key(|m| m.inspections);
     monkeys
         .iter()
@@ -35,22 +69,6 @@ fn part2(input: &str) -> u64 {
         .product()
 }
 
-fn parse_monkeys(input: &str, ridiculous: bool) -> Vec<Monkey> {
-    let mut monkeys: Vec<Monkey> = input
-        .split("\n\n")
-        .map(|s| {
-            parse_monkey(s, ridiculous)
-                .expect("failed to parse monkey")
-                .1
-        })
-        .collect();
-    if ridiculous {
-        let modulo = monkeys.iter().map(|m| m.test).product();
-        monkeys.iter_mut().for_each(|m| m.modulo = modulo);
-    }
-    monkeys
-}
-
 fn round(monkeys: &mut [Monkey]) {
     for i in 0..monkeys.len() {
         monkeys[i].turn().iter().for_each(|(item, dest)| {
@@ -60,7 +78,7 @@ fn round(monkeys: &mut [Monkey]) {
 }
 
 #[derive(Clone, Debug)]
-struct Monkey {
+pub struct Monkey {
     items: Vec<u64>,
     operation: Operation,
     test: u64,
@@ -107,7 +125,7 @@ impl Monkey {
     }
 
     fn item_destination(&self, item: u64) -> usize {
-        if item % self.test == 0 {
+        if item.is_multiple_of(self.test) {
             self.if_true
         } else {
             self.if_false
@@ -116,38 +134,34 @@ impl Monkey {
 }
 
 #[derive(Copy, Clone, Debug)]
-enum Operation {
+pub enum Operation {
     Times(OpAmount),
     Plus(OpAmount),
 }
 
 #[derive(Copy, Clone, Debug)]
-enum OpAmount {
+pub enum OpAmount {
     Old,
     Num(u64),
 }
 
-fn parse_monkey(i: &str, ridiculous: bool) -> IResult<&str, Monkey> {
+fn parse_monkey(i: &str) -> IResult<&str, Monkey> {
     let (i, _) = tag("Monkey ")(i)?;
     let (i, _) = u8(i)?;
     let (i, _) = tag(":\n  Starting items: ")(i)?;
     let (i, items) = separated_list1(tag(", "), u64)(i)?;
     let (i, _) = tag("\n  Operation: new = old ")(i)?;
     let (i, op) = alt((tag("+ "), tag("* ")))(i)?;
-    let (i, op_amount) = alt((digit1, tag("old")))(i)?;
+    let (i, op_amount) = alt((map(u64, OpAmount::Num), value(OpAmount::Old, tag("old"))))(i)?;
     let (i, _) = tag("\n  Test: divisible by ")(i)?;
     let (i, test) = u64(i)?;
     let (i, _) = tag("\n    If true: throw to monkey ")(i)?;
     let (i, if_true) = u8(i)?;
     let (i, _) = tag("\n    If false: throw to monkey ")(i)?;
     let (i, if_false) = u8(i)?;
-    let operation_amount = match op_amount {
-        "old" => OpAmount::Old,
-        n => OpAmount::Num(n.parse().unwrap()),
-    };
     let operation = match op {
-        "+ " => Operation::Plus(operation_amount),
-        "* " => Operation::Times(operation_amount),
+        "+ " => Operation::Plus(op_amount),
+        "* " => Operation::Times(op_amount),
         _ => unreachable!(),
     };
     Ok((
@@ -159,7 +173,7 @@ fn parse_monkey(i: &str, ridiculous: bool) -> IResult<&str, Monkey> {
             if_true: if_true as usize,
             if_false: if_false as usize,
             inspections: 0,
-            ridiculous,
+            ridiculous: false,
             modulo: 0,
         },
     ))
@@ -201,11 +215,11 @@ Monkey 3:
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 10605);
+        assert_eq!(Day11::part1(&Day11::parse(INPUT).unwrap()), Ok(10605));
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(INPUT), 2713310158);
+        assert_eq!(Day11::part2(&Day11::parse(INPUT).unwrap()), Ok(2713310158));
     }
 }
@@ -5,21 +5,32 @@ use std::str::FromStr;
 
 use fxhash::FxHashSet;
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/12.txt");
-    format!("{}\n{}", part1(input), part2(input))
-}
+use crate::{Error, Result, Solution};
 
-fn part1(input: &str) -> usize {
-    let map: Map = input.parse().expect("invalid map");
-    let mut walker = Walker::new(&map, 'S'.into());
-    walker.walk().expect("no route found")
-}
+pub const INPUT: &str = include_str!("../inputs/12.txt");
+
+pub struct Day12;
+
+impl Solution for Day12 {
+    type Input = Map;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    const DAY: u8 = 12;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        input.parse()
+    }
 
-fn part2(input: &str) -> usize {
-    let map: Map = input.parse().expect("invalid map");
-    let mut walker = Walker::new(&map, 'a'.into());
-    walker.walk().expect("no route found")
+    fn part1(map: &Self::Input) -> Result<usize> {
+        let mut walker = Walker::new(map, 'S'.into());
+        walker.walk().ok_or(Error::new("no route found"))
+    }
+
+    fn part2(map: &Self::Input) -> Result<usize> {
+        let mut walker = Walker::new(map, 'a'.into());
+        walker.walk().ok_or(Error::new("no route found"))
+    }
 }
 
 #[derive(Debug)]
@@ -71,29 +82,44 @@ impl<'a> Walker<'a> {
     }
 }
 
+/// A height map, with elevations as characters.
 #[derive(Clone, Debug)]
-struct Map {
+pub struct Map {
     inner: Vec<u32>,
     width: usize,
     end: usize,
 }
 
 impl FromStr for Map {
-    type Err = &'static str;
+    type Err = Error;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    fn from_str(s: &str) -> Result<Self> {
+        let width = s
+            .lines()
+            .next()
+            .ok_or(Error::new("zero width map"))?
+            .chars()
+            .count();
+        for (y, line) in s.lines().enumerate() {
+            if let Some((x, _)) = line.char_indices().find(|(_, c)| !c.is_ascii_alphabetic()) {
+                return Err(Error::new("invalid elevation").located(s, &line[x..]));
+            }
+            if line.chars().count() != width {
+                return Err(Error::new("map is not rectangular").at(y + 1, 1));
+            }
+        }
         let end = s
             .chars()
             .filter(|c| c.is_alphabetic())
             .position(|c| c == 'E')
-            .ok_or("unable to find end")?;
+            .ok_or(Error::new("unable to find end"))?;
         Ok(Map {
             inner: s
                 .chars()
                 .filter(|c| c.is_alphabetic())
                 .map(|c| c.into())
                 .collect(),
-            width: s.lines().next().ok_or("zero width map")?.chars().count(),
+            width,
             end,
         })
     }
@@ -164,11 +190,11 @@ abdefghi
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 31);
+        assert_eq!(Day12::part1(&Day12::parse(INPUT).unwrap()), Ok(31));
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(INPUT), 29);
+        assert_eq!(Day12::part2(&Day12::parse(INPUT).unwrap()), Ok(29));
     }
 }
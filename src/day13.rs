@@ -5,62 +5,74 @@ use nom::{
     multi::separated_list0, sequence::delimited, IResult,
 };
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/13.txt");
-    format!("{}\n{}", part1(input), part2(input))
-}
+use crate::error::{nom, parse_lines};
+use crate::{Error, Result, Solution};
+
+pub const INPUT: &str = include_str!("../inputs/13.txt");
+
+pub struct Day13;
 
-fn part1(input: &str) -> usize {
-    let messages = parse_messages(input);
-    (0..messages.len())
-        .step_by(2)
-        .enumerate()
-        .map(|(n, i)| {
-            if messages[i] < messages[i + 1] {
-                n + 1
-            } else {
-                0
+impl Solution for Day13 {
+    /// All messages in order, without the pairing.
+    type Input = Vec<Message>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    const DAY: u8 = 13;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        let mut messages = vec![];
+        for lines in input.trim_end().split("\n\n") {
+            let pair = parse_lines(lines, nom(parse_message, "message"))
+                .map_err(|e| e.within(input, lines))?;
+            if pair.len() != 2 {
+                return Err(Error::new("expected a pair of messages").within(input, lines));
             }
-        })
-        .sum()
-}
+            messages.extend(pair);
+        }
+        Ok(messages)
+    }
 
-fn part2(input: &str) -> usize {
-    let mut messages = parse_messages(input);
-    messages.push(parse_message("[[2]]").unwrap().1);
-    messages.push(parse_message("[[6]]").unwrap().1);
-    messages.sort();
-    let a = messages
-        .iter()
-        .position(|m| *m == parse_message("[[2]]").unwrap().1)
-        .expect("unable to find package");
-    let b = messages
-        .iter()
-        .position(|m| *m == parse_message("[[6]]").unwrap().1)
-        .expect("unable to find package");
-    (a + 1) * (b + 1)
-}
+    fn part1(messages: &Self::Input) -> Result<usize> {
+        Ok((0..messages.len())
+            .step_by(2)
+            .enumerate()
+            .map(|(n, i)| {
+                if messages[i] < messages[i + 1] {
+                    n + 1
+                } else {
+                    0
+                }
+            })
+            .sum())
+    }
 
-fn parse_messages(input: &str) -> Vec<Message> {
-    input
-        .split("\n\n")
-        .flat_map(|pair| -> Vec<Message> {
-            pair.lines()
-                .map(|msg| parse_message(msg).expect("failed to parse message").1)
-                .collect()
-        })
-        .collect()
+    fn part2(messages: &Self::Input) -> Result<usize> {
+        let mut messages = messages.clone();
+        messages.push(parse_message("[[2]]").unwrap().1);
+        messages.push(parse_message("[[6]]").unwrap().1);
+        messages.sort();
+        let a = messages
+            .iter()
+            .position(|m| *m == parse_message("[[2]]").unwrap().1)
+            .expect("unable to find package");
+        let b = messages
+            .iter()
+            .position(|m| *m == parse_message("[[6]]").unwrap().1)
+            .expect("unable to find package");
+        Ok((a + 1) * (b + 1))
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
-enum Message {
+pub enum Message {
     Atom(u8),
     List(Vec<Message>),
 }
 
 impl PartialOrd for Message {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(cmp_messages(self, other).unwrap_or(Ordering::Equal))
+        Some(self.cmp(other))
     }
 }
 
@@ -142,11 +154,11 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 13);
+        assert_eq!(Day13::part1(&Day13::parse(INPUT).unwrap()), Ok(13));
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(INPUT), 140);
+        assert_eq!(Day13::part2(&Day13::parse(INPUT).unwrap()), Ok(140));
     }
 }
@@ -1,49 +1,99 @@
 use nom::{
-    bytes::complete::tag,
-    character::complete::u16,
-    combinator::{all_consuming, map},
-    multi::separated_list1,
-    sequence::tuple,
-    IResult,
+    bytes::complete::tag, character::complete::u16, combinator::map, multi::separated_list1,
+    sequence::tuple, IResult,
 };
 
-/// Cave depth needs to be at least the lowest rock y-position, plus
-/// some padding for part 2. In my case that is somewhere around 170,
-/// but YMMV.
-const CAVE_DEPTH: usize = 255;
+use crate::error::{nom, parse_lines};
+use crate::{Error, Result, Solution};
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/14.txt");
-    format!("{}\n{}", part1(input), part2(input))
-}
+pub const INPUT: &str = include_str!("../inputs/14.txt");
+
+pub struct Day14;
+
+/// Where the sand pours into the cave from.
+pub const SOURCE: Point = Point { x: 500, y: 0 };
+
+impl Solution for Day14 {
+    /// The rock paths of the scan.
+    type Input = Vec<Path>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    const DAY: u8 = 14;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_lines(input, |l| {
+            let path = nom(parse_path, "path")(l)?;
+            check_path(&path)?;
+            Ok(path)
+        })
+    }
 
-fn part1(input: &str) -> usize {
-    let mut cave = Cave::new(parse_paths(input));
-    cave.drop_sand_until_terminal()
+    fn part1(paths: &Self::Input) -> Result<usize> {
+        Ok(sand_at_rest(paths, &SOURCE, false))
+    }
+
+    fn part2(paths: &Self::Input) -> Result<usize> {
+        Ok(sand_at_rest(paths, &SOURCE, true))
+    }
 }
 
-fn part2(input: &str) -> usize {
-    let mut paths = parse_paths(input);
-    let floor = 2 + paths
-        .iter()
-        .map(|path| path.iter().map(|point| point.y).max().unwrap())
-        .max()
-        .unwrap();
-    paths.push(vec![Point { x: 0, y: floor }, Point { x: 1000, y: floor }]);
-    let mut cave = Cave::new(paths);
-    cave.drop_sand_until_terminal()
+/// Returns how many units of sand come to rest when poured in from
+/// a source, before sand falls into the void or blocks the source.
+/// With a floor, there is one two below the lowest rock or the
+/// source, whichever is lower.
+pub fn sand_at_rest(paths: &[Path], source: &Point, floor: bool) -> usize {
+    Cave::new(paths, source, floor).drop_sand_until_terminal()
 }
 
 #[derive(Debug)]
 struct Cave {
-    inner: [Cell; 1000 * CAVE_DEPTH],
+    inner: Vec<Cell>,
+    /// The x-position of the leftmost column.
+    left: isize,
+    width: usize,
+    /// The y-position of the lowest rock, or the source if that is
+    /// lower. Without a floor, sand below it falls forever.
+    bottom: usize,
+    floor: bool,
+    source: (isize, usize),
 }
 
 impl Cave {
     /// Returns a newly constructed cave with rock as indicated by
-    /// paths.
-    fn new(paths: Vec<Path>) -> Self {
-        let mut inner = [Cell::Empty; 1000 * CAVE_DEPTH];
+    /// paths, and optionally a floor two below the lowest rock. The
+    /// cave is just big enough for all the sand that can come to rest
+    /// in it.
+    fn new(paths: &[Path], source: &Point, floor: bool) -> Self {
+        let points = || paths.iter().flatten().chain([source]);
+        let bottom = points().map(|p| p.y).max().unwrap_or_default();
+        // Sand spreads at most one step sideways for each step down,
+        // and it stops at the floor, or falls through below the
+        // bottom.
+        let spread = (bottom + 1 - source.y) as isize;
+        let (source_x, source_y) = (source.x as isize, source.y);
+        let left = points()
+            .map(|p| p.x as isize)
+            .min()
+            .unwrap_or_default()
+            .min(source_x - spread)
+            - 1;
+        let right = points()
+            .map(|p| p.x as isize)
+            .max()
+            .unwrap_or_default()
+            .max(source_x + spread)
+            + 1;
+        let width = (right - left + 1) as usize;
+
+        let mut cave = Self {
+            inner: vec![Cell::Empty; width * (bottom + 3)],
+            left,
+            width,
+            bottom,
+            floor,
+            source: (source_x, source_y),
+        };
         for path in paths {
             for i in 0..path.len() - 1 {
                 let start = &path[i];
@@ -51,17 +101,28 @@ impl Cave {
                 if start.x == end.x {
                     // Vertical section
                     for y in start.y.min(end.y)..=start.y.max(end.y) {
-                        inner[idx(start.x, y)] = Cell::Rock;
+                        let idx = cave.idx(start.x as isize, y);
+                        cave.inner[idx] = Cell::Rock;
                     }
                 } else {
                     // Horizontal section
                     for x in start.x.min(end.x)..=start.x.max(end.x) {
-                        inner[idx(x, start.y)] = Cell::Rock;
+                        let idx = cave.idx(x as isize, start.y);
+                        cave.inner[idx] = Cell::Rock;
                     }
                 }
             }
         }
-        Self { inner }
+        if floor {
+            let floor = (bottom + 2) * width;
+            cave.inner[floor..].fill(Cell::Rock);
+        }
+        cave
+    }
+
+    /// Returns the cave index for a given x, y coordinate pair.
+    fn idx(&self, x: isize, y: usize) -> usize {
+        y * self.width + (x - self.left) as usize
     }
 
     /// Keeps dropping sand into the cave until it either falls out
@@ -79,45 +140,41 @@ impl Cave {
     /// through into the void below, or came to rest in front of the
     /// inlet.
     fn drop_one_unit_of_sand(&mut self) -> bool {
-        let (mut x, mut y) = (500, 0);
+        let (mut x, mut y) = self.source;
+        // We're overflowing.
+        if self.inner[self.idx(x, y)] != Cell::Empty {
+            return true;
+        }
         loop {
             // Fell through.
-            if y >= CAVE_DEPTH - 1 {
-                return true;
-            }
-            // We're overflowing.
-            if self.inner[idx(x, y)] == Cell::Sand {
+            if !self.floor && y >= self.bottom {
                 return true;
             }
             // Try falling straight down.
-            if self.inner[idx(x, y + 1)] == Cell::Empty {
+            if self.inner[self.idx(x, y + 1)] == Cell::Empty {
                 y += 1;
                 continue;
             }
             // Try falling to the left.
-            if self.inner[idx(x - 1, y + 1)] == Cell::Empty {
+            if self.inner[self.idx(x - 1, y + 1)] == Cell::Empty {
                 x -= 1;
                 y += 1;
                 continue;
             }
             // Try falling to the right.
-            if self.inner[idx(x + 1, y + 1)] == Cell::Empty {
+            if self.inner[self.idx(x + 1, y + 1)] == Cell::Empty {
                 x += 1;
                 y += 1;
                 continue;
             }
             // Settled.
-            self.inner[idx(x, y)] = Cell::Sand;
+            let idx = self.idx(x, y);
+            self.inner[idx] = Cell::Sand;
             return false;
         }
     }
 }
 
-/// Returns the cave index for a given x, y coordinate pair.
-fn idx(x: usize, y: usize) -> usize {
-    y * 1000 + x
-}
-
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 enum Cell {
     Empty,
@@ -125,27 +182,27 @@ enum Cell {
     Sand,
 }
 
-type Path = Vec<Point>;
+pub type Path = Vec<Point>;
 
-fn parse_paths(input: &str) -> Vec<Path> {
-    input
-        .lines()
-        .map(|l| {
-            all_consuming(parse_path)(l)
-                .expect("failed to parse path")
-                .1
-        })
-        .collect()
+/// Checks that a path only has straight sections.
+fn check_path(path: &Path) -> Result<()> {
+    if path
+        .windows(2)
+        .any(|w| w[0].x != w[1].x && w[0].y != w[1].y)
+    {
+        return Err(Error::new("path has a diagonal section"));
+    }
+    Ok(())
 }
 
 fn parse_path(i: &str) -> IResult<&str, Path> {
     separated_list1(tag(" -> "), parse_point)(i)
 }
 
-#[derive(Debug)]
-struct Point {
-    x: usize,
-    y: usize,
+#[derive(Clone, Debug)]
+pub struct Point {
+    pub x: usize,
+    pub y: usize,
 }
 
 fn parse_point(i: &str) -> IResult<&str, Point> {
@@ -165,11 +222,37 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 24);
+        assert_eq!(Day14::part1(&Day14::parse(INPUT).unwrap()), Ok(24));
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(INPUT), 93);
+        assert_eq!(Day14::part2(&Day14::parse(INPUT).unwrap()), Ok(93));
+    }
+
+    #[test]
+    fn other_sources() {
+        let paths = Day14::parse(INPUT).unwrap();
+        // Pouring in far from the rock at x = 0, the sand fills a
+        // triangle down to the floor.
+        assert_eq!(sand_at_rest(&paths, &Point { x: 0, y: 0 }, true), 11 * 11);
+        // Pouring in from below all the rock.
+        let below = Point { x: 500, y: 20 };
+        assert_eq!(sand_at_rest(&paths, &below, false), 0);
+        assert_eq!(sand_at_rest(&paths, &below, true), 4);
+        // Without any rock, sand only ever settles on the floor.
+        assert_eq!(sand_at_rest(&[], &SOURCE, false), 0);
+        assert_eq!(sand_at_rest(&[], &SOURCE, true), 4);
+        assert_eq!(Day14::part1(&vec![]), Ok(0));
+        assert_eq!(Day14::part2(&vec![]), Ok(4));
+    }
+
+    #[test]
+    fn large_scan() {
+        // Deeper and wider than the old fixed cave. The sand piles up
+        // on the rock, and never reaches the floor.
+        let paths = Day14::parse("0,300 -> 1000,300").unwrap();
+        assert_eq!(Day14::part1(&paths), Ok(300 * 300));
+        assert_eq!(Day14::part2(&paths), Ok(300 * 300));
     }
 }
@@ -1,14 +1,37 @@
 use fxhash::FxHashSet;
-use nom::{bytes::complete::tag, character::complete::i32, combinator::all_consuming, IResult};
+use nom::{bytes::complete::tag, character::complete::i32, IResult};
 use rayon::prelude::*;
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/15.txt");
-    format!("{}\n{}", part1(input, 2_000_000), part2(input, 4_000_000))
+use crate::error::{nom, parse_lines};
+use crate::{Error, Result, Solution};
+
+pub const INPUT: &str = include_str!("../inputs/15.txt");
+
+pub struct Day15;
+
+impl Solution for Day15 {
+    type Input = Vec<Sensor>;
+    type Part1 = usize;
+    type Part2 = i64;
+
+    const DAY: u8 = 15;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_lines(input, nom(parse_sensor, "sensor"))
+    }
+
+    fn part1(sensors: &Self::Input) -> Result<usize> {
+        Ok(covered_in_row(sensors, 2_000_000))
+    }
+
+    fn part2(sensors: &Self::Input) -> Result<i64> {
+        tuning_frequency(sensors, 4_000_000)
+    }
 }
 
-fn part1(input: &str, row: i32) -> usize {
-    let (sensors, _beacons) = parse_sensors_and_beacons(input);
+/// Returns the number of positions in a row that cannot contain a
+/// beacon.
+pub fn covered_in_row(sensors: &[Sensor], row: i32) -> usize {
     let mut ranges: Vec<_> = sensors.iter().map(|s| s.row_coverage(row)).collect();
     ranges.sort_by_key(|c| c.0);
     let mut combined_ranges = vec![];
@@ -29,35 +52,24 @@ fn part1(input: &str, row: i32) -> usize {
         .sum()
 }
 
-fn part2(input: &str, limits: i32) -> i64 {
-    let (sensors, beacons) = parse_sensors_and_beacons(input);
+/// Finds the only position within 0..=limits on both axes that is
+/// not covered by any sensor, and returns its tuning frequency.
+pub fn tuning_frequency(sensors: &[Sensor], limits: i32) -> Result<i64> {
+    let beacons: FxHashSet<_> = sensors.iter().map(|s| s.closest_beacon).collect();
     let (x, y) = sensors
         .par_iter()
-        .flat_map(|s| s.just_out_of_reach(limits, &sensors))
+        .flat_map(|s| s.just_out_of_reach(limits, sensors))
         .find_any(|b| !beacons.contains(b))
-        .expect("failed to find beacon");
-    x as i64 * 4_000_000 + y as i64
-}
-
-fn parse_sensors_and_beacons(input: &str) -> (Vec<Sensor>, FxHashSet<(i32, i32)>) {
-    let sensors: Vec<_> = input
-        .lines()
-        .map(|l| {
-            all_consuming(parse_sensor)(l)
-                .expect("failed to parse sensor")
-                .1
-        })
-        .collect();
-    let beacons: FxHashSet<_> = sensors.iter().map(|s| s.closest_beacon).collect();
-    (sensors, beacons)
+        .ok_or(Error::new("failed to find beacon"))?;
+    Ok(x as i64 * 4_000_000 + y as i64)
 }
 
 #[derive(Debug)]
-struct Sensor {
-    x: i32,
-    y: i32,
-    closest_beacon: (i32, i32),
-    detection_range: i32,
+pub struct Sensor {
+    pub x: i32,
+    pub y: i32,
+    pub closest_beacon: (i32, i32),
+    pub detection_range: i32,
 }
 
 impl Sensor {
@@ -149,11 +161,14 @@ Sensor at x=20, y=1: closest beacon is at x=15, y=3
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT, 10), 26);
+        assert_eq!(covered_in_row(&Day15::parse(INPUT).unwrap(), 10), 26);
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(INPUT, 20), 56000011);
+        assert_eq!(
+            tuning_frequency(&Day15::parse(INPUT).unwrap(), 20),
+            Ok(56000011)
+        );
     }
 }
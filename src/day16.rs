@@ -1,343 +1,484 @@
-use std::collections::VecDeque;
-
-use fxhash::{FxHashMap, FxHashSet};
-use itertools::Itertools;
+use crate::error::{nom, parse_lines};
+use crate::{Error, Result, Solution};
+use fxhash::FxHashMap;
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{alpha1, u16},
-    combinator::{all_consuming, map},
+    character::complete::{alpha1, u32},
+    combinator::map,
     multi::separated_list1,
     IResult,
 };
-use rayon::prelude::*;
-
-pub fn solve() -> String {
-    let input = include_str!("../inputs/16.txt");
-    let (valves, distances) = setup(input);
-    format!(
-        "{}\n{}",
-        part1(&valves, &distances),
-        part2(&valves, &distances)
-    )
-}
 
-fn part1(
-    valves: &FxHashMap<String, Valve>,
-    distances: &FxHashMap<(String, String), Time>,
-) -> Pressure {
-    let volcano = Volcano::new(vec![Agent::default()], 30, valves, distances);
-    volcano.release_pressure()
-}
+pub const INPUT: &str = include_str!("../inputs/16.txt");
+
+pub struct Day16;
+
+impl Solution for Day16 {
+    type Input = Tunnels;
+    type Part1 = Pressure;
+    type Part2 = Pressure;
+
+    const DAY: u8 = 16;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        Tunnels::new(parse_lines(input, nom(parse_valve, "valve"))?)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Pressure> {
+        Ok(best_plan(input, &["AA"], 30)?.pressure)
+    }
 
-fn part2(
-    valves: &FxHashMap<String, Valve>,
-    distances: &FxHashMap<(String, String), Time>,
-) -> Pressure {
-    let volcano = Volcano::new(
-        vec![Agent::default(), Agent::default()],
-        26,
-        valves,
-        distances,
-    );
-    volcano.release_pressure()
+    fn part2(input: &Self::Input) -> Result<Pressure> {
+        Ok(best_plan(input, &["AA"; 2], 26)?.pressure)
+    }
 }
 
-fn setup(input: &str) -> (FxHashMap<String, Valve>, FxHashMap<(String, String), Time>) {
-    let valves: FxHashMap<_, _> = input
-        .lines()
-        .map(|l| {
-            all_consuming(parse_valve)(l)
-                .expect("failed to parse valve")
-                .1
-        })
-        .map(|v| (v.name.clone(), v))
-        .collect();
-    // These are the valves that can actually be opened, as well as
-    // the starting position. We use this to pre-calculate distances
-    // between useful graph nodes. The useless valves inbetween are
-    // just used to modify edge weights.
-    let mut useful_valves: Vec<Valve> = valves
-        .values()
-        .cloned()
-        .filter(|v| v.flow_rate > 0)
-        .collect();
-    useful_valves.push(valves.get("AA").unwrap().clone());
-    let distances: FxHashMap<_, _> = useful_valves
-        .iter()
-        .permutations(2)
-        .par_bridge()
-        .map(|vs| {
-            (
-                (vs[0].name.clone(), vs[1].name.clone()),
-                vs[0].eta(&vs[1].name, &valves),
-            )
-        })
+/// The largest number of valves with a flow rate we can plan for.
+const MAX_VALVES: usize = 20;
+
+/// Returns the plan that releases the most pressure, with an agent
+/// starting at each of the given valves and a shared time budget.
+pub fn best_plan<'a>(tunnels: &'a Tunnels, starts: &[&str], time: Time) -> Result<Plan<'a>> {
+    let useful: Vec<usize> = (0..tunnels.names.len())
+        .filter(|&v| tunnels.flow_rates[v] > 0)
         .collect();
-    (valves, distances)
-}
+    if useful.len() > MAX_VALVES {
+        return Err(Error::new(format!(
+            "too many valves with a flow rate ({})",
+            useful.len()
+        )));
+    }
+    // The time it takes to reach and open each useful valve.
+    let etas = |from: usize| -> Vec<Time> {
+        useful
+            .iter()
+            .map(|&to| tunnels.distances[from][to].saturating_add(1))
+            .collect()
+    };
+    let search = Search {
+        flow: useful.iter().map(|&v| tunnels.flow_rates[v]).collect(),
+        distances: useful.iter().map(|&v| etas(v)).collect(),
+        time,
+    };
 
-type Time = u8;
-type Pressure = u16;
-
-#[derive(Clone, Debug)]
-struct Volcano<'a> {
-    agents: Vec<Agent<'a>>,
-    opened: FxHashSet<&'a str>,
-    time_remaining: Time,
-    pressure_released: Pressure,
-    valves: &'a FxHashMap<String, Valve>,
-    distances: &'a FxHashMap<(String, String), Time>,
-}
+    // Find the best tours for each agent on its own. Agents that
+    // start in the same place share them.
+    let mut agents = vec![];
+    let mut tours = FxHashMap::default();
+    for start in starts {
+        let start = tunnels
+            .index(start)
+            .ok_or_else(|| Error::new(format!("no valve {start}")))?;
+        let etas = etas(start);
+        tours.entry(start).or_insert_with(|| search.tours(&etas));
+        agents.push((start, etas));
+    }
 
-impl<'a> Volcano<'a> {
-    fn new(
-        agents: Vec<Agent<'a>>,
-        time_remaining: Time,
-        valves: &'a FxHashMap<String, Valve>,
-        distances: &'a FxHashMap<(String, String), Time>,
-    ) -> Self {
-        Self {
-            agents,
-            opened: FxHashSet::default(),
-            time_remaining,
-            pressure_released: 0,
-            valves,
-            distances,
+    // Then share the valves out between the agents: the most
+    // pressure the first agents can release between them using only
+    // the valves in each set, and the valves left to the last one.
+    let full = (1 << useful.len()) - 1;
+    let mut best = vec![0; full + 1];
+    let mut choices = vec![];
+    for (k, (start, _)) in agents.iter().enumerate() {
+        let tours = &tours[start];
+        let mut next = vec![0; full + 1];
+        let mut choice = vec![0; full + 1];
+        // Only the full set matters once all agents are planned for.
+        let first = if k == agents.len() - 1 { full } else { 0 };
+        for set in first..=full {
+            if k == 0 {
+                next[set] = tours.best[set].0;
+                choice[set] = set;
+                continue;
+            }
+            // Try every subset of the valves for this agent.
+            let mut subset = set;
+            loop {
+                let pressure = best[set ^ subset] + tours.best[subset].0;
+                if pressure > next[set] {
+                    next[set] = pressure;
+                    choice[set] = subset;
+                }
+                if subset == 0 {
+                    break;
+                }
+                subset = (subset - 1) & set;
+            }
         }
+        best = next;
+        choices.push(choice);
     }
 
-    /// Returns the maximum amount of pressure that can be released in
-    /// this volcano.
-    fn release_pressure(self) -> Pressure {
-        let mut rv = 0;
-        self.pass_time(&mut rv);
-        rv
+    // Work back through the choices to find each agent's tour.
+    let mut set = full;
+    let mut steps = vec![];
+    for (agent, (start, etas)) in agents.iter().enumerate().rev() {
+        let tours = &tours[start];
+        let subset = choices[agent][set];
+        set ^= subset;
+        let mut minute = 0;
+        let mut position = etas;
+        for valve in tours.order(tours.best[subset].1) {
+            minute += position[valve];
+            steps.push(Step {
+                agent,
+                valve: tunnels.names[useful[valve]].as_str(),
+                arrival: minute - 1,
+                opened: minute,
+                pressure: search.flow[valve] * (time - minute) as Pressure,
+            });
+            position = &search.distances[valve];
+        }
     }
+    steps.sort_by_key(|s| (s.opened, s.agent));
+    Ok(Plan {
+        starts: agents
+            .iter()
+            .map(|(start, _)| tunnels.names[*start].as_str())
+            .collect(),
+        time,
+        pressure: steps.iter().map(|s| s.pressure).sum(),
+        steps,
+    })
+}
+
+/// A valve opened by one of the agents.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Step<'a> {
+    /// The index of the agent opening the valve.
+    pub agent: usize,
+    pub valve: &'a str,
+    /// The minute at the end of which the agent reaches the valve.
+    pub arrival: Time,
+    /// The minute during which the agent opens the valve.
+    pub opened: Time,
+    /// The pressure the valve releases in the time that is left.
+    pub pressure: Pressure,
+}
 
-    /// Called the next time at least one agent is idle, and thus
-    /// needs to get moving.
-    fn pass_time(self, acc: &mut Pressure) {
-        // If there is no possible way of surpassing the current best
-        // found option, just abort right here.
-        let potential: u16 = self
-            .valves
-            .values()
-            .filter_map(|v| {
-                if self.opened.contains(v.name.as_str()) {
-                    None
-                } else {
-                    let best_eta = self
-                        .agents
+/// The valves to open, in the order they are opened.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Plan<'a> {
+    /// The valve each agent starts at.
+    pub starts: Vec<&'a str>,
+    pub time: Time,
+    pub pressure: Pressure,
+    pub steps: Vec<Step<'a>>,
+}
+
+impl Plan<'_> {
+    /// Re-simulates the plan minute by minute, walking the tunnels to
+    /// each valve, and returns the pressure released. Fails if the
+    /// plan can't be carried out in time.
+    pub fn validate(&self, tunnels: &Tunnels) -> Result<Pressure> {
+        let time = self.time;
+        let index = |name| {
+            tunnels
+                .index(name)
+                .ok_or_else(|| Error::new(format!("unknown valve {name}")))
+        };
+        // Where each agent is, and the minute it is free to move on.
+        let mut positions = self
+            .starts
+            .iter()
+            .map(|start| Ok((index(start)?, 0)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut opened = vec![None; tunnels.names.len()];
+        for step in &self.steps {
+            let valve = index(step.valve)?;
+            let (position, free) = positions
+                .get_mut(step.agent)
+                .ok_or_else(|| Error::new(format!("unknown agent {}", step.agent)))?;
+            // Walk the tunnels, one minute at a time, until the agent
+            // reaches the valve.
+            let mut reached = vec![false; tunnels.names.len()];
+            reached[*position] = true;
+            let mut minute = *free;
+            while !reached[valve] {
+                minute += 1;
+                if minute > step.arrival {
+                    return Err(Error::new(format!(
+                        "agent {} can't reach {} by minute {}",
+                        step.agent, step.valve, step.arrival
+                    )));
+                }
+                for from in (0..reached.len())
+                    .filter(|&v| reached[v])
+                    .collect::<Vec<_>>()
+                {
+                    tunnels.leads_to[from]
                         .iter()
-                        .map(|agent| {
-                            self.distances
-                                .get(&(agent.position.to_string(), v.name.to_string()))
-                                .unwrap_or(&Time::MAX)
-                        })
-                        .min()
-                        .unwrap();
-                    Some(
-                        v.flow_rate
-                            * ((self.time_remaining as u16).saturating_sub(*best_eta as u16)),
-                    )
+                        .for_each(|&to| reached[to] = true);
                 }
-            })
-            .sum();
-        if self.pressure_released + potential <= *acc {
-            return;
+            }
+            if step.opened <= step.arrival || step.opened > time {
+                return Err(Error::new(format!(
+                    "{} is opened in minute {}, which is not after arriving in minute {} and \
+                     before the time runs out",
+                    step.valve, step.opened, step.arrival
+                )));
+            }
+            if opened[valve].replace(step.opened).is_some() {
+                return Err(Error::new(format!("{} is opened twice", step.valve)));
+            }
+            *position = valve;
+            *free = step.opened;
         }
 
-        // Collect the different valves that each agent could open
-        // next.
-        let mut agent_options: Vec<_> = (0..self.agents.len()).map(|_| Vec::default()).collect();
-        for (idx, agent) in self.agents.iter().enumerate() {
-            if agent.busy_until < self.time_remaining {
-                continue;
-            }
-            for candidate in self.next_valve_candidates(agent) {
-                agent_options[idx].push((idx, candidate));
+        // Release pressure from every open valve, minute by minute.
+        let mut released = 0;
+        for minute in 1..=time {
+            released += (0..tunnels.names.len())
+                .filter(|&v| opened[v].is_some_and(|opened| opened < minute))
+                .map(|v| tunnels.flow_rates[v])
+                .sum::<Pressure>();
+        }
+        for step in &self.steps {
+            let pressure =
+                tunnels.flow_rates[index(step.valve)?] * (time - step.opened) as Pressure;
+            if pressure != step.pressure {
+                return Err(Error::new(format!(
+                    "{} releases {pressure}, not {}",
+                    step.valve, step.pressure
+                )));
             }
-            agent_options[idx].sort_by_key(|(_, x)| u16::MAX - x.2);
         }
+        if released != self.pressure {
+            return Err(Error::new(format!(
+                "plan releases {released}, not {}",
+                self.pressure
+            )));
+        }
+        Ok(released)
+    }
+}
 
-        let idle_agents = agent_options.iter().filter(|o| !o.is_empty()).count();
-        *acc = (*acc).max(self.pressure_released);
-
-        if idle_agents == 1 {
-            // If there is only one idle agent, branch out for all
-            // valves this agent could open next.
-            for (idx, (candidate, time_taken, pressure_released)) in
-                agent_options.iter().find(|v| !v.is_empty()).unwrap()
-            {
-                // If there is no possible way of surpassing the
-                // current best found option, just abort right here.
-                // This is a bit of a mess, because it basically
-                // simulates one step ahead before we do an
-                // expensive-ish clone of the whole volcano. The
-                // functionality is similar, but not the same as the
-                // version above.
-                let potential: u16 = self
-                    .valves
-                    .values()
-                    .filter_map(|v| {
-                        if &v.name == candidate || self.opened.contains(v.name.as_str()) {
-                            None
-                        } else {
-                            let eta_from_candidate = self
-                                .distances
-                                .get(&(candidate.to_string(), v.name.to_string()))
-                                .unwrap_or(&Time::MAX);
-                            let best_agent_eta = self
-                                .agents
-                                .iter()
-                                .enumerate()
-                                .filter_map(|(i, a)| {
-                                    if i != *idx {
-                                        Some(
-                                            self.distances
-                                                .get(&(a.position.to_string(), v.name.to_string()))
-                                                .map(|t| *t + self.time_remaining - a.busy_until)
-                                                .unwrap_or(Time::MAX),
-                                        )
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .min()
-                                .unwrap_or(Time::MAX);
-                            let best_eta = eta_from_candidate.min(&best_agent_eta);
-                            Some(
-                                v.flow_rate
-                                    * ((self.time_remaining as u16)
-                                        .saturating_sub(*time_taken as u16)
-                                        .saturating_sub(*best_eta as u16)),
-                            )
-                        }
-                    })
-                    .sum();
-                if self.pressure_released + pressure_released + potential <= *acc {
-                    continue;
-                }
+/// The tunnel network, with the valves numbered in the order they
+/// are listed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Tunnels {
+    pub names: Vec<String>,
+    pub flow_rates: Vec<Pressure>,
+    pub leads_to: Vec<Vec<usize>>,
+    /// The time it takes to walk from one valve to another, or
+    /// Time::MAX if there is no way there.
+    pub distances: Vec<Vec<Time>>,
+    indices: FxHashMap<String, usize>,
+}
 
-                let mut new_volcano = self.clone();
-                let this_agent = &mut new_volcano.agents[*idx];
-                this_agent.position = candidate;
-                this_agent.busy_until = new_volcano.time_remaining - time_taken;
-                new_volcano.time_remaining = new_volcano
-                    .agents
-                    .iter()
-                    .map(|a| a.busy_until)
-                    .max()
-                    .unwrap_or_default();
-                new_volcano.opened.insert(candidate);
-                new_volcano.pressure_released += pressure_released;
-                new_volcano.pass_time(acc);
+impl Tunnels {
+    pub fn new(valves: Vec<Valve>) -> Result<Self> {
+        let mut indices = FxHashMap::default();
+        for (idx, valve) in valves.iter().enumerate() {
+            if indices.insert(valve.name.clone(), idx).is_some() {
+                return Err(Error::new(format!("duplicate valve {}", valve.name)));
             }
-        } else {
-            // If there are several idle agents, build a catesian
-            // product of their options, so that they try all
-            // combinations of remaining valves, where they can't open
-            // the same valve next.
-            for options in agent_options
+        }
+        let mut leads_to = vec![];
+        for valve in &valves {
+            let to = valve
+                .leads_to
                 .iter()
-                .multi_cartesian_product()
-                .filter(|opts| opts[0].1 .0 != opts[1].1 .0)
-            {
-                let mut new_volcano = self.clone();
-                for (agent_idx, (next_valve, time_taken, pressure_released)) in options {
-                    let this_agent = &mut new_volcano.agents[*agent_idx];
-                    this_agent.position = next_valve;
-                    this_agent.busy_until = new_volcano.time_remaining - time_taken;
-                    new_volcano.opened.insert(next_valve);
-                    new_volcano.pressure_released += pressure_released;
+                .map(|to| {
+                    indices.get(to).copied().ok_or_else(|| {
+                        Error::new(format!("valve {} leads to unknown valve {to}", valve.name))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            leads_to.push(to);
+        }
+
+        // Floyd-Warshall, every tunnel takes a minute.
+        let n = valves.len();
+        let mut distances = vec![vec![Time::MAX; n]; n];
+        for (from, to) in leads_to.iter().enumerate() {
+            to.iter().for_each(|&to| distances[from][to] = 1);
+            distances[from][from] = 0;
+        }
+        for via in 0..n {
+            for from in 0..n {
+                for to in 0..n {
+                    let distance = distances[from][via].saturating_add(distances[via][to]);
+                    if distance < distances[from][to] {
+                        distances[from][to] = distance;
+                    }
                 }
-                new_volcano.time_remaining = new_volcano
-                    .agents
-                    .iter()
-                    .map(|a| a.busy_until)
-                    .max()
-                    .unwrap_or_default();
-                new_volcano.pass_time(acc);
             }
         }
+
+        Ok(Self {
+            names: valves.iter().map(|v| v.name.clone()).collect(),
+            flow_rates: valves.iter().map(|v| v.flow_rate).collect(),
+            leads_to,
+            distances,
+            indices,
+        })
     }
 
-    /// Returns a Vec of valves that could be opened next, along with
-    /// the time that would take, and the pressure that would be
-    /// released in the time remaining.
-    fn next_valve_candidates(&self, agent: &Agent) -> Vec<(String, Time, Pressure)> {
-        self.valves
-            .values()
-            .filter(|v| v.flow_rate > 0)
-            .filter(|v| !self.opened.contains(v.name.as_str()))
-            .filter_map(|v| {
-                let time_required: Time = *self
-                    .distances
-                    .get(&(agent.position.to_string(), v.name.to_string()))
-                    .unwrap();
-                if time_required <= self.time_remaining {
-                    Some((
-                        v.name.clone(),
-                        time_required,
-                        (self.time_remaining - time_required) as Pressure * v.flow_rate,
-                    ))
-                } else {
-                    None
-                }
-            })
-            .collect()
+    /// Returns the number of a valve by its name.
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.indices.get(name).copied()
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
-struct Agent<'a> {
-    position: &'a str,
-    busy_until: Time,
+/// Flow rates fit in a u32, so even opening every valve at minute
+/// zero can't release more pressure than a u64 holds.
+pub type Time = u16;
+pub type Pressure = u64;
+
+/// A set of valves, as a bitmask of their indices.
+type Set = usize;
+
+/// The valves with a flow rate, and the time it takes to reach and
+/// open each one from the others.
+struct Search {
+    flow: Vec<Pressure>,
+    distances: Vec<Vec<Time>>,
+    time: Time,
+}
+
+/// A way to open a set of valves that ends at one of them.
+#[derive(Clone, Copy)]
+struct Tour {
+    /// The valve opened last.
+    last: usize,
+    /// The minute the last valve is opened.
+    time: Time,
+    /// The pressure the valves would have released had they been
+    /// open from minute zero, but weren't.
+    lost: Pressure,
+    /// The index of the tour of the other valves that this one
+    /// continues, or None if the last valve was the first.
+    previous: Option<usize>,
 }
 
-impl<'a> Default for Agent<'a> {
-    fn default() -> Self {
-        Self {
-            position: "AA",
-            busy_until: 30,
-        }
-    }
+/// The most pressure a single agent can release opening only the
+/// valves in each set, with the exact set that does it, and the
+/// tours that open exactly each set.
+struct Tours {
+    best: Vec<(Pressure, Set)>,
+    ends: Vec<Option<usize>>,
+    fronts: Vec<Vec<Tour>>,
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-struct Valve {
-    name: String,
-    flow_rate: Pressure,
-    leads_to: Vec<String>,
+impl Tours {
+    /// Returns the order in which to open exactly the given set of
+    /// valves for the best tour that opens them.
+    fn order(&self, mut set: Set) -> Vec<usize> {
+        let mut order = vec![];
+        let mut end = self.ends[set];
+        while let Some(index) = end {
+            let tour = self.fronts[set][index];
+            order.push(tour.last);
+            end = tour.previous;
+            set ^= 1 << tour.last;
+        }
+        order.reverse();
+        order
+    }
 }
 
-impl Valve {
-    /// Returns the number of minutes it takes to reach and open
-    /// another valve when standing in front of this one.
-    fn eta(&self, to: &str, valves: &FxHashMap<String, Valve>) -> Time {
-        let mut options = VecDeque::default();
-        self.leads_to.iter().for_each(|v| options.push_back((v, 1)));
-        while let Some((name, distance)) = options.pop_front() {
-            if name == to {
-                return distance + 1;
+impl Search {
+    /// Returns the best tours for an agent that takes the given times
+    /// to reach and open each valve from where it starts.
+    ///
+    /// A tour is only worth keeping if no other tour of the same set
+    /// ending at the same valve both finishes sooner and loses less
+    /// pressure, so the work grows with the number of sets rather
+    /// than with the time budget.
+    fn tours(&self, etas: &[Time]) -> Tours {
+        let valves = self.flow.len();
+        let sets = 1 << valves;
+        let mut tours = Tours {
+            best: vec![(0, 0); sets],
+            ends: vec![None; sets],
+            fronts: vec![vec![]; sets],
+        };
+        let start = Tour {
+            last: 0,
+            time: 0,
+            lost: 0,
+            previous: None,
+        };
+        for (valve, &eta) in etas.iter().enumerate() {
+            self.extend(&mut tours, 0, &start, valve, eta);
+        }
+        // Adding a valve only ever makes a set larger, so each set's
+        // tours are complete before any are extended.
+        let mut total_flow = vec![0; sets];
+        for set in 1..sets {
+            let lowest = set.trailing_zeros() as usize;
+            total_flow[set] = total_flow[set & (set - 1)] + self.flow[lowest];
+            for index in 0..tours.fronts[set].len() {
+                let tour = Tour {
+                    previous: Some(index),
+                    ..tours.fronts[set][index]
+                };
+                let pressure = self.time as Pressure * total_flow[set] - tour.lost;
+                if pressure > tours.best[set].0 {
+                    tours.best[set] = (pressure, set);
+                    tours.ends[set] = Some(index);
+                }
+                for (valve, &eta) in self.distances[tour.last].iter().enumerate() {
+                    if set & 1 << valve == 0 {
+                        self.extend(&mut tours, set, &tour, valve, eta);
+                    }
+                }
             }
-            valves
-                .get(name)
-                .unwrap()
-                .leads_to
-                .iter()
-                .for_each(|v| options.push_back((v, distance + 1)));
         }
-        panic!("Failed to find a path to target valve");
+        // A set is as good as the best of its subsets.
+        for set in 0..sets {
+            for valve in 0..valves {
+                if set & 1 << valve != 0 && tours.best[set ^ 1 << valve].0 > tours.best[set].0 {
+                    tours.best[set] = tours.best[set ^ 1 << valve];
+                }
+            }
+        }
+        tours
     }
+
+    /// Records a tour that opens the given valve, arriving after the
+    /// given tour of a set, unless it runs out of time or another
+    /// tour is at least as good.
+    fn extend(&self, tours: &mut Tours, set: Set, after: &Tour, valve: usize, eta: Time) {
+        let time = after.time.saturating_add(eta);
+        if time >= self.time {
+            return;
+        }
+        let tour = Tour {
+            last: valve,
+            time,
+            lost: after.lost + self.flow[valve] * time as Pressure,
+            previous: after.previous,
+        };
+        let front = &mut tours.fronts[set | 1 << valve];
+        let rivals = |t: &Tour| t.last == tour.last;
+        if front
+            .iter()
+            .any(|t| rivals(t) && t.time <= tour.time && t.lost <= tour.lost)
+        {
+            return;
+        }
+        front.retain(|t| !rivals(t) || t.time < tour.time || t.lost < tour.lost);
+        front.push(tour);
+    }
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// A valve as listed in the scan, before the tunnels are numbered.
+pub struct Valve {
+    pub name: String,
+    pub flow_rate: Pressure,
+    pub leads_to: Vec<String>,
 }
 
 fn parse_valve(i: &str) -> IResult<&str, Valve> {
     let (i, _) = tag("Valve ")(i)?;
     let (i, name) = map(alpha1, str::to_string)(i)?;
     let (i, _) = tag(" has flow rate=")(i)?;
-    let (i, flow_rate) = u16(i)?;
+    let (i, flow_rate) = map(u32, Pressure::from)(i)?;
     let (i, _) = alt((
         tag("; tunnel leads to valve "),
         tag("; tunnels lead to valves "),
@@ -371,13 +512,131 @@ Valve JJ has flow rate=21; tunnel leads to valve II";
 
     #[test]
     fn part1_example() {
-        let (v, d) = setup(INPUT);
-        assert_eq!(part1(&v, &d), 1651);
+        assert_eq!(Day16::part1(&Day16::parse(INPUT).unwrap()), Ok(1651));
     }
 
     #[test]
     fn part2_example() {
-        let (v, d) = setup(INPUT);
-        assert_eq!(part2(&v, &d), 1707);
+        assert_eq!(Day16::part2(&Day16::parse(INPUT).unwrap()), Ok(1707));
+    }
+
+    #[test]
+    fn tunnels() {
+        let tunnels = Day16::parse(INPUT).unwrap();
+        let distance =
+            |from, to| tunnels.distances[tunnels.index(from).unwrap()][tunnels.index(to).unwrap()];
+        assert_eq!(distance("AA", "AA"), 0);
+        assert_eq!(distance("AA", "DD"), 1);
+        assert_eq!(distance("HH", "JJ"), 7);
+        assert_eq!(distance("JJ", "HH"), 7);
+
+        let tunnels = Day16::parse(
+            "Valve AA has flow rate=0; tunnel leads to valve BB\n\
+                                    Valve BB has flow rate=1; tunnel leads to valve BB",
+        )
+        .unwrap();
+        assert_eq!(tunnels.distances, [[0, 1], [Time::MAX, 0]]);
+        assert_eq!(
+            Day16::parse("Valve AA has flow rate=0; tunnel leads to valve BB"),
+            Err(Error::new("valve AA leads to unknown valve BB"))
+        );
+        assert_eq!(
+            Day16::parse(
+                "Valve AA has flow rate=0; tunnel leads to valve AA\n\
+                          Valve AA has flow rate=1; tunnel leads to valve AA"
+            ),
+            Err(Error::new("duplicate valve AA"))
+        );
+    }
+
+    #[test]
+    fn plans() {
+        let input = Day16::parse(INPUT).unwrap();
+        let plan = best_plan(&input, &["AA"], 30).unwrap();
+        // The plan from the puzzle description.
+        let steps: Vec<_> = plan
+            .steps
+            .iter()
+            .map(|s| (s.valve, s.arrival, s.opened, s.pressure))
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                ("DD", 1, 2, 20 * 28),
+                ("BB", 4, 5, 13 * 25),
+                ("JJ", 8, 9, 21 * 21),
+                ("HH", 16, 17, 22 * 13),
+                ("EE", 20, 21, 3 * 9),
+                ("CC", 23, 24, 2 * 6),
+            ]
+        );
+        assert_eq!(plan.validate(&input), Ok(1651));
+
+        let plan = best_plan(&input, &["AA"; 2], 26).unwrap();
+        assert_eq!(plan.validate(&input), Ok(1707));
+        assert_eq!(plan.steps.iter().filter(|s| s.agent == 1).count(), 3);
+    }
+
+    #[test]
+    fn invalid_plans() {
+        let tunnels = Day16::parse(INPUT).unwrap();
+        let step = |valve, arrival, opened, pressure| Step {
+            agent: 0,
+            valve,
+            arrival,
+            opened,
+            pressure,
+        };
+        let plan = |steps: Vec<Step<'static>>| Plan {
+            starts: vec!["AA"],
+            time: 30,
+            pressure: steps.iter().map(|s| s.pressure).sum(),
+            steps,
+        };
+        assert_eq!(
+            plan(vec![step("HH", 4, 5, 22 * 25)]).validate(&tunnels),
+            Err(Error::new("agent 0 can't reach HH by minute 4"))
+        );
+        assert_eq!(
+            plan(vec![step("DD", 1, 2, 560), step("DD", 2, 3, 540)]).validate(&tunnels),
+            Err(Error::new("DD is opened twice"))
+        );
+        assert_eq!(
+            plan(vec![step("DD", 1, 2, 500)]).validate(&tunnels),
+            Err(Error::new("DD releases 560, not 500"))
+        );
+    }
+
+    #[test]
+    fn other_starts() {
+        let input = Day16::parse(INPUT).unwrap();
+        // Opening HH right away, then DD and BB on the way back.
+        let plan = best_plan(&input, &["HH"], 10).unwrap();
+        assert_eq!(plan.validate(&input), Ok(22 * 9 + 20 * 4 + 13));
+        let plan = best_plan(&input, &["HH", "JJ"], 10).unwrap();
+        assert_eq!(plan.validate(&input), Ok(544));
+        assert_eq!(
+            best_plan(&input, &["ZZ"], 10),
+            Err(Error::new("no valve ZZ"))
+        );
+        assert_eq!(best_plan(&input, &[], 10).map(|p| p.pressure), Ok(0));
+    }
+
+    #[test]
+    fn more_agents() {
+        let input = Day16::parse(super::INPUT).unwrap();
+        for (agents, pressure) in [(1, 1314), (2, 2316), (3, 3131), (4, 3366)] {
+            let plan = best_plan(&input, &vec!["AA"; agents], 26).unwrap();
+            assert_eq!(plan.validate(&input), Ok(pressure));
+        }
+    }
+
+    #[test]
+    fn long_budgets() {
+        let input = Day16::parse(super::INPUT).unwrap();
+        for (agents, time, pressure) in [(2, 100, 17009), (1, 1000, 194993)] {
+            let plan = best_plan(&input, &vec!["AA"; agents], time).unwrap();
+            assert_eq!(plan.validate(&input), Ok(pressure));
+        }
     }
 }
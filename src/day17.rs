@@ -1,89 +1,65 @@
-pub fn solve() -> String {
-    let input = include_str!("../inputs/17.txt");
-    format!("{}\n{}", part1(input), part2(input))
-}
+use fxhash::FxHashMap;
 
-fn part1(input: &str) -> usize {
-    let jets = parse_jets(input);
-    let mut jets = jets.iter().copied().cycle();
-    let mut chamber = Chamber::new(&mut jets);
-    (0..2022).for_each(|_| chamber.spawn_piece());
-    chamber.real_height()
-}
+use crate::{Error, Result, Solution};
 
-fn part2(input: &str) -> u128 {
-    let jets = parse_jets(input);
-    let mut infinite_jets = jets.iter().copied().cycle();
-    let mut chamber = Chamber::new(&mut infinite_jets);
+pub const INPUT: &str = include_str!("../inputs/17.txt");
 
-    // 1T is way too many iterations. But because inputs are
-    // deterministic, we know it can loop. This records a pattern of 5
-    // heights, which repeats eventually. With that we can extrapolate
-    // total height gain.
+pub struct Day17;
 
-    // Run to first clear and record that height.
-    chamber.spawn_piece();
-    let mut base_iterations = 1;
-    while chamber.height() != 0 {
-        chamber.spawn_piece();
-        base_iterations += 1;
-    }
-    let base_height = chamber.real_height();
-
-    // Record a cycle pattern of pieces to clear.
-    let mut repetition_pattern: Vec<usize> = vec![];
-    let mut loop_iterations = 0u128;
-    for i in 0.. {
-        if i > 0 && chamber.height() == 0 {
-            repetition_pattern.push(i - repetition_pattern.iter().sum::<usize>());
-            // NB My input only clears rows twice per cycle, so I can
-            // use a very short pattern, other inputs might require
-            // larger patterns.
-            if repetition_pattern.len() == 2 {
-                break;
-            }
-        }
-        loop_iterations += 1;
-        chamber.spawn_piece();
+impl Solution for Day17 {
+    type Input = Vec<Jet>;
+    type Part1 = usize;
+    type Part2 = u128;
+
+    const DAY: u8 = 17;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_jets(input)
     }
 
-    // Try to find that pattern again.
-    let mut pattern_matches = 0;
-    let mut last_clear = 0;
-    for i in 0.. {
-        if i > 0 && chamber.height() == 0 {
-            if repetition_pattern[pattern_matches] == i - last_clear {
-                pattern_matches += 1;
-            } else {
-                pattern_matches = 0;
-            }
-            if pattern_matches == repetition_pattern.len() - 1 {
-                break;
-            }
-            last_clear = i;
-        }
-        loop_iterations += 1;
-        chamber.spawn_piece();
+    fn part1(jets: &Self::Input) -> Result<usize> {
+        Ok(height_after(jets, 2022) as usize)
     }
 
-    let post_loop_height = chamber.real_height();
-    let loop_gain = post_loop_height - base_height;
+    fn part2(jets: &Self::Input) -> Result<u128> {
+        Ok(height_after(jets, 1_000_000_000_000))
+    }
+}
 
-    // Finish up the last bit to get to 1T iterations.
-    let loops = (1_000_000_000_000u128 - base_iterations) / loop_iterations;
-    let extra_iterations = (1_000_000_000_000u128 - base_iterations) % loop_iterations;
-    for _ in 0..extra_iterations {
+/// Returns the height of the tower after a number of pieces have
+/// fallen.
+///
+/// Far too many pieces fall to simulate them all, but the pieces and
+/// jets repeat, so the tower eventually does too. Once a piece is
+/// about to fall with the same piece, jet and top of the tower as
+/// before, each cycle after that gains the same height.
+pub fn height_after(jets: &[Jet], pieces: u128) -> u128 {
+    let mut chamber = Chamber::new(jets);
+    // The height after each number of pieces.
+    let mut heights = vec![0];
+    let mut seen = FxHashMap::default();
+    while ((heights.len() - 1) as u128) < pieces {
+        let dropped = heights.len() - 1;
+        if let Some(start) = seen.insert(chamber.state(), dropped) {
+            let period = (dropped - start) as u128;
+            let gain = (heights[dropped] - heights[start]) as u128;
+            let remaining = pieces - dropped as u128;
+            let extra = heights[start + (remaining % period) as usize] - heights[start];
+            return heights[dropped] as u128 + remaining / period * gain + extra as u128;
+        }
         chamber.spawn_piece();
+        heights.push(chamber.real_height());
     }
-
-    let extra_height = chamber.real_height() - post_loop_height;
-
-    base_height as u128 + loops * loop_gain as u128 + extra_height as u128
+    heights[pieces as usize] as u128
 }
 
 struct Chamber<'a> {
     source: TetrisPieceSource,
-    jets: &'a mut dyn Iterator<Item = Jet>,
+    jets: &'a [Jet],
+    /// The index of the next jet.
+    jet: usize,
+    /// The number of pieces dropped so far.
+    pieces: usize,
     /// Each row is the lower 7 bits, where a set bit is a piece. The
     /// first row is the bottom-most.
     inner: Vec<u8>,
@@ -94,10 +70,12 @@ struct Chamber<'a> {
 }
 
 impl<'a> Chamber<'a> {
-    fn new(jets: &'a mut impl Iterator<Item = Jet>) -> Self {
+    fn new(jets: &'a [Jet]) -> Self {
         Self {
             source: TetrisPieceSource::default(),
             jets,
+            jet: 0,
+            pieces: 0,
             inner: Vec::default(),
             unreachable: 0,
         }
@@ -115,10 +93,30 @@ impl<'a> Chamber<'a> {
         self.inner.len() - self.inner.iter().rev().take_while(|&r| *r == 0).count()
     }
 
+    /// Returns what decides how the rest of the tower is built: the
+    /// next piece and jet, and the skyline. The skyline is the rows
+    /// from the lowest of the columns' tops up, as falling pieces
+    /// rarely reach further down.
+    fn state(&self) -> (usize, usize, Vec<u8>) {
+        let height = self.height();
+        let mut bottom = height;
+        let mut columns = 0;
+        while columns != 0b01111111 && bottom > 0 {
+            bottom -= 1;
+            columns |= self.inner[bottom];
+        }
+        (
+            self.pieces % 5,
+            self.jet,
+            self.inner[bottom..height].to_vec(),
+        )
+    }
+
     /// Spans the next piece and simulates it falling until it comes
     /// to rest somewhere, modifying the internal state.
     fn spawn_piece(&mut self) {
         let piece = self.source.next().unwrap();
+        self.pieces += 1;
         let mut x: usize = 2;
         let mut y: usize = self.height() + 3;
 
@@ -130,7 +128,9 @@ impl<'a> Chamber<'a> {
         let mut dropped = 0;
         loop {
             // Apply the jet.
-            let proposed_x = match self.jets.next().unwrap() {
+            let jet = self.jets[self.jet];
+            self.jet = (self.jet + 1) % self.jets.len();
+            let proposed_x = match jet {
                 Jet::Left => x.saturating_sub(1),
                 Jet::Right => (7 - piece.width()).min(x + 1),
             };
@@ -164,18 +164,21 @@ impl<'a> Chamber<'a> {
 }
 
 #[derive(Copy, Clone, Debug)]
-enum Jet {
+pub enum Jet {
     Left,
     Right,
 }
 
-fn parse_jets(s: &str) -> Vec<Jet> {
-    s.trim()
-        .chars()
-        .map(|c| match c {
-            '<' => Jet::Left,
-            '>' => Jet::Right,
-            _ => panic!("invalid jet"),
+fn parse_jets(s: &str) -> Result<Vec<Jet>> {
+    let jets = s.trim();
+    if jets.is_empty() {
+        return Err(Error::new("no jets"));
+    }
+    jets.char_indices()
+        .map(|(i, c)| match c {
+            '<' => Ok(Jet::Left),
+            '>' => Ok(Jet::Right),
+            _ => Err(Error::new("invalid jet").located(s, &jets[i..])),
         })
         .collect()
 }
@@ -269,6 +272,28 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 3068);
+        assert_eq!(Day17::part1(&Day17::parse(INPUT).unwrap()), Ok(3068));
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(
+            Day17::part2(&Day17::parse(INPUT).unwrap()),
+            Ok(1514285714288)
+        );
+    }
+
+    #[test]
+    fn height_after_matches_simulation() {
+        for input in [INPUT, super::INPUT] {
+            let jets = Day17::parse(input).unwrap();
+            let mut chamber = Chamber::new(&jets);
+            for pieces in 0..5000 {
+                if pieces % 97 == 0 {
+                    assert_eq!(height_after(&jets, pieces), chamber.real_height() as u128);
+                }
+                chamber.spawn_piece();
+            }
+        }
     }
 }
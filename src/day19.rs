@@ -1,293 +1,443 @@
-use nom::{bytes::complete::tag, character::complete::u8, combinator::all_consuming, IResult};
+use std::fmt;
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/19.txt");
-    format!("{}\n{}", part1(input), part2(input))
-}
+use itertools::Itertools;
+use nom::{
+    bytes::complete::tag,
+    character::complete::{alpha1, char, u32, u8},
+    multi::{many1, separated_list1},
+    sequence::{delimited, preceded, separated_pair, terminated, tuple},
+    IResult,
+};
 
-fn part1(input: &str) -> u32 {
-    let blueprints: Vec<Blueprint> = input
-        .lines()
-        .map(|l| {
-            all_consuming(parse_blueprint)(l)
-                .expect("failed to parse blueprint")
-                .1
-        })
-        .collect();
+use crate::error::{nom, parse_lines};
+use crate::{Error, Result, Solution};
 
-    blueprints.iter().map(Blueprint::quality_level).sum()
-}
+pub const INPUT: &str = include_str!("../inputs/19.txt");
+
+pub struct Day19;
+
+impl Solution for Day19 {
+    type Input = Vec<Blueprint>;
+    type Part1 = u32;
+    type Part2 = u32;
+
+    const DAY: u8 = 19;
 
-fn part2(input: &str) -> u32 {
-    let blueprints: Vec<Blueprint> = input
-        .lines()
-        .take(3)
-        .map(|l| {
-            all_consuming(parse_blueprint)(l)
-                .expect("failed to parse blueprint")
-                .1
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_lines(input, |l| {
+            let (id, recipes) = nom(parse_blueprint, "blueprint")(l)?;
+            Blueprint::new(id, &recipes)
         })
-        .collect();
+    }
 
-    blueprints
-        .iter()
-        .map(|bp| bp.max_geodes(32) as u32)
-        .product()
-}
+    fn part1(blueprints: &Self::Input) -> Result<u32> {
+        Ok(blueprints.iter().map(Blueprint::quality_level).sum())
+    }
 
-type Ore = u8;
-type Clay = u8;
-type Obsidian = u8;
-type Geodes = u8;
+    fn part2(blueprints: &Self::Input) -> Result<u32> {
+        Ok(blueprints
+            .iter()
+            .take(3)
+            .map(|bp| bp.most_collected(32))
+            .product())
+    }
+}
 
+/// What it takes to build each kind of robot. Each robot collects
+/// one resource, and there is a robot for each resource.
 #[derive(Debug)]
-struct Blueprint {
-    id: u8,
-    ore_robot_cost: Ore,
-    clay_robot_cost: Ore,
-    obsidian_robot_cost: (Ore, Clay),
-    geode_robot_cost: (Ore, Obsidian),
+pub struct Blueprint {
+    pub id: u8,
+    /// The names of the resources, and the robots collecting them.
+    pub resources: Vec<String>,
+    /// For each kind of robot, the amount of each resource it costs.
+    pub costs: Vec<Vec<u32>>,
+    /// The resource to collect as much of as possible. This is geodes
+    /// if there are any, and otherwise the last resource.
+    pub objective: usize,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 struct EvaluationState {
-    time: u8,
-    ore: u8,
-    clay: u8,
-    obsidian: u8,
-    geodes: u8,
-    ore_robots: u8,
-    clay_robots: u8,
-    obsidian_robots: u8,
-    geode_robots: u8,
+    time: u32,
+    resources: Vec<u32>,
+    robots: Vec<u32>,
+}
+
+/// The bookkeeping of a search for the most of the objective.
+#[derive(Debug)]
+struct Search {
+    time_limit: u32,
+    /// The most robots of each kind that could ever be useful.
+    max_robots: Vec<u32>,
+    most_collected: u32,
+    /// The earliest time a robot collecting the objective was built.
+    earliest_objective: u32,
+    /// The robots built to reach the current state, with the minute
+    /// each was built in.
+    builds: Vec<(u32, usize)>,
+    /// The builds that collect the most.
+    best: Vec<(u32, usize)>,
+    /// Room to work out an upper bound in, so that it doesn't need
+    /// to allocate for every state.
+    scratch: EvaluationState,
+    affordable: Vec<bool>,
 }
 
 impl Blueprint {
+    /// Creates a blueprint from the robot recipes, each of which is
+    /// the name of a robot and what it costs.
+    pub fn new<'a>(id: u8, recipes: &[(&'a str, Vec<(u32, &'a str)>)]) -> Result<Self> {
+        let resources: Vec<_> = recipes.iter().map(|(name, _)| *name).collect();
+        let index = |name: &str| resources.iter().position(|r| *r == name);
+        let mut costs = vec![];
+        for (i, (robot, cost)) in recipes.iter().enumerate() {
+            if index(robot) != Some(i) {
+                return Err(Error::new(format!("duplicate {robot} robot")));
+            }
+            let mut amounts = vec![0; resources.len()];
+            for (amount, resource) in cost {
+                let i = index(resource)
+                    .ok_or_else(|| Error::new(format!("no robot collects {resource}")))?;
+                amounts[i] += amount;
+            }
+            costs.push(amounts);
+        }
+        Ok(Self {
+            id,
+            objective: index("geode").unwrap_or(resources.len() - 1),
+            resources: resources.into_iter().map(String::from).collect(),
+            costs,
+        })
+    }
+
     /// Calculates the quality level for part 1.
-    fn quality_level(&self) -> u32 {
-        self.id as u32 * self.max_geodes(24) as u32
+    pub fn quality_level(&self) -> u32 {
+        self.id as u32 * self.most_collected(24)
     }
 
-    /// Returns the maximum number of geodes that can be cracked in a
+    /// Returns the most of the objective that can be collected in a
     /// given time.
-    fn max_geodes(&self, time_limit: u8) -> u8 {
-        let mut most_geodes = 0;
-        let mut earliest_geode = 25;
-
-        let initial_state = EvaluationState {
-            time: 0,
-            ore: 0,
-            clay: 0,
-            obsidian: 0,
-            geodes: 0,
-            ore_robots: 1,
-            clay_robots: 0,
-            obsidian_robots: 0,
-            geode_robots: 0,
-        };
-        self.find_max_geodes(
-            initial_state,
-            time_limit,
-            &mut most_geodes,
-            &mut earliest_geode,
-        );
-        most_geodes
+    pub fn most_collected(&self, time_limit: u32) -> u32 {
+        self.plan(time_limit).collected()
     }
 
-    /// Returns the maximum per-minute ore production we'd ever need.
-    fn max_ore_need(&self) -> Ore {
-        *[
-            self.ore_robot_cost,
-            self.clay_robot_cost,
-            self.obsidian_robot_cost.0,
-            self.geode_robot_cost.0,
-        ]
-        .iter()
-        .max()
-        .unwrap()
+    /// Returns the schedule that collects the most of the objective in
+    /// a given time.
+    pub fn plan(&self, time_limit: u32) -> Schedule {
+        let mut search = Search {
+            time_limit,
+            max_robots: self.max_robots(),
+            most_collected: 0,
+            earliest_objective: time_limit,
+            builds: vec![],
+            best: vec![],
+            scratch: self.initial_state(),
+            affordable: vec![false; self.resources.len()],
+        };
+        self.find_most(&mut self.initial_state(), &mut search);
+        self.schedule(&search.best, time_limit)
     }
 
-    /// Recursively evaluates all reasonable build orders to find the
-    /// maximum amount of geodes that can be produced.
-    fn find_max_geodes(
-        &self,
-        state: EvaluationState,
-        time_limit: u8,
-        most_geodes: &mut Geodes,
-        earliest_geode: &mut u8,
-    ) {
-        let time_left = (time_limit - state.time) as u32;
-        if (state.time > *earliest_geode && state.geode_robots == 0)
-            || state.geodes as u32
-                + time_left * state.geode_robots as u32
-                + (1..=time_left).sum::<u32>()
-                < *most_geodes as u32
-        {
-            return;
-        } else if state.geode_robots > 0 {
-            *earliest_geode = (*earliest_geode).min(state.time);
+    /// We start out with a single robot collecting the first
+    /// resource.
+    fn initial_state(&self) -> EvaluationState {
+        let mut robots = vec![0; self.resources.len()];
+        robots[0] = 1;
+        EvaluationState {
+            time: 0,
+            resources: vec![0; self.resources.len()],
+            robots,
         }
+    }
 
-        // Returns the time it takes to reach a resource goal at
-        // current production levels, plus 1 to actually build a
-        // robot.
-        let time_to_resource = |current: u8, target: u8, production: u8| -> u8 {
-            if production == 0 {
-                u8::MAX
-            } else if target <= current {
-                1
-            } else {
-                div_ceil(target.saturating_sub(current), production) + 1
+    /// Plays out building robots in the given minutes, and returns
+    /// what happens every minute.
+    fn schedule(&self, builds: &[(u32, usize)], time_limit: u32) -> Schedule {
+        let EvaluationState {
+            mut resources,
+            mut robots,
+            ..
+        } = self.initial_state();
+        let mut builds = builds.iter().peekable();
+        let mut minutes = vec![];
+        for minute in 1..=time_limit {
+            let build = builds.next_if(|(m, _)| *m == minute).map(|&(_, r)| r);
+            if let Some(robot) = build {
+                for (resource, cost) in resources.iter_mut().zip(&self.costs[robot]) {
+                    *resource -= cost;
+                }
             }
-        };
-
-        // Returns the time it takes to build a robot given
-        // time_to_resource-style tuples.
-        let time_to_robot = |resources: &[(u8, u8, u8)]| -> u8 {
-            resources
-                .iter()
-                .map(|(c, t, p)| time_to_resource(*c, *t, *p))
-                .max()
-                .unwrap()
-        };
-
-        let geode_costs = [
-            (state.ore, self.geode_robot_cost.0, state.ore_robots),
-            (
-                state.obsidian,
-                self.geode_robot_cost.1,
-                state.obsidian_robots,
-            ),
-        ];
-        let obsidian_costs = [
-            (state.ore, self.obsidian_robot_cost.0, state.ore_robots),
-            (state.clay, self.obsidian_robot_cost.1, state.clay_robots),
-        ];
-        let clay_costs = [(state.ore, self.clay_robot_cost, state.ore_robots)];
-        let ore_costs = [(state.ore, self.ore_robot_cost, state.ore_robots)];
-
-        // Times to get the next robots if we just wait.
-        let time_to_geode = time_to_robot(&geode_costs);
-        let time_to_obsidian = time_to_robot(&obsidian_costs);
-        let time_to_clay = time_to_robot(&clay_costs);
-        let time_to_ore = time_to_robot(&ore_costs);
-
-        if state.time.saturating_add(time_to_geode) <= time_limit {
-            let mut fork = state;
-            fork.time += time_to_geode;
-
-            fork.ore += fork.ore_robots * time_to_geode;
-            fork.clay += fork.clay_robots * time_to_geode;
-            fork.obsidian += fork.obsidian_robots * time_to_geode;
-            fork.geodes += fork.geode_robots * time_to_geode;
-
-            fork.geode_robots += 1;
-            fork.ore -= self.geode_robot_cost.0;
-            fork.obsidian -= self.geode_robot_cost.1;
-
-            *most_geodes = (*most_geodes).max(fork.geodes);
-
-            if fork.time < time_limit {
-                self.find_max_geodes(fork, time_limit, most_geodes, earliest_geode);
+            for (resource, n) in resources.iter_mut().zip(&robots) {
+                *resource += n;
+            }
+            if let Some(robot) = build {
+                robots[robot] += 1;
             }
+            minutes.push(Minute {
+                minute,
+                build,
+                resources: resources.clone(),
+                robots: robots.clone(),
+            });
         }
-        if state.obsidian_robots < self.geode_robot_cost.1
-            && state.time.saturating_add(time_to_obsidian) <= time_limit
-        {
-            let mut fork = state;
-            fork.time += time_to_obsidian;
-
-            fork.ore += fork.ore_robots * time_to_obsidian;
-            fork.clay += fork.clay_robots * time_to_obsidian;
-            fork.obsidian += fork.obsidian_robots * time_to_obsidian;
-            fork.geodes += fork.geode_robots * time_to_obsidian;
-
-            fork.obsidian_robots += 1;
-            fork.ore -= self.obsidian_robot_cost.0;
-            fork.clay -= self.obsidian_robot_cost.1;
+        Schedule {
+            resources: self.resources.clone(),
+            costs: self.costs.clone(),
+            objective: self.objective,
+            minutes,
+        }
+    }
 
-            *most_geodes = (*most_geodes).max(fork.geodes);
+    /// Returns the most robots of each kind we'd ever need. As we can
+    /// only build one robot a minute, there's no use in collecting
+    /// more of a resource a minute than the most any robot costs.
+    fn max_robots(&self) -> Vec<u32> {
+        (0..self.resources.len())
+            .map(|resource| {
+                if resource == self.objective {
+                    u32::MAX
+                } else {
+                    self.costs.iter().map(|c| c[resource]).max().unwrap_or(0)
+                }
+            })
+            .collect()
+    }
 
-            if fork.time < time_limit {
-                self.find_max_geodes(fork, time_limit, most_geodes, earliest_geode);
+    /// Returns the time it takes to collect the resources for a robot
+    /// at current production levels, plus 1 to actually build it.
+    /// Returns None if we don't collect a resource it needs.
+    fn time_to_robot(&self, state: &EvaluationState, robot: usize) -> Option<u32> {
+        let mut wait = 0;
+        for (resource, &cost) in self.costs[robot].iter().enumerate() {
+            let (current, production) = (state.resources[resource], state.robots[resource]);
+            if cost <= current {
+                continue;
+            } else if production == 0 {
+                return None;
             }
+            wait = wait.max((cost - current).div_ceil(production));
         }
-        if state.clay_robots < self.obsidian_robot_cost.1
-            && state.time.saturating_add(time_to_clay) <= time_limit
-        {
-            let mut fork = state;
-            fork.time += time_to_clay;
+        Some(wait + 1)
+    }
 
-            fork.ore += fork.ore_robots * time_to_clay;
-            fork.clay += fork.clay_robots * time_to_clay;
-            fork.obsidian += fork.obsidian_robots * time_to_clay;
-            fork.geodes += fork.geode_robots * time_to_clay;
+    /// Returns an upper bound on how much of the objective can be
+    /// collected from a state. If resources were never spent, and we
+    /// could build every robot we can afford each minute, we'd never
+    /// have fewer resources or robots than by sticking to the rules.
+    fn most_possible(&self, state: &EvaluationState, search: &mut Search) -> u32 {
+        let EvaluationState {
+            resources, robots, ..
+        } = &mut search.scratch;
+        resources.clone_from(&state.resources);
+        robots.clone_from(&state.robots);
+        for _ in state.time..search.time_limit {
+            for (affordable, cost) in search.affordable.iter_mut().zip(&self.costs) {
+                *affordable = cost.iter().zip(resources.iter()).all(|(c, r)| c <= r);
+            }
+            for ((resource, robots), affordable) in resources
+                .iter_mut()
+                .zip(robots.iter_mut())
+                .zip(&search.affordable)
+            {
+                *resource += *robots;
+                *robots += *affordable as u32;
+            }
+        }
+        resources[self.objective]
+    }
 
-            fork.clay_robots += 1;
-            fork.ore -= self.clay_robot_cost;
+    /// Recursively evaluates all reasonable build orders to find the
+    /// most of the objective that can be collected.
+    fn find_most(&self, state: &mut EvaluationState, search: &mut Search) {
+        let objective = self.objective;
+        let time_left = search.time_limit - state.time;
+        if (state.time > search.earliest_objective && state.robots[objective] == 0)
+            || state.resources[objective]
+                + time_left * state.robots[objective]
+                + time_left * (time_left + 1) / 2
+                < search.most_collected
+            || self.most_possible(state, search) <= search.most_collected
+        {
+            return;
+        } else if state.robots[objective] > 0 {
+            search.earliest_objective = search.earliest_objective.min(state.time);
+        }
 
-            *most_geodes = (*most_geodes).max(fork.geodes);
+        // Try the objective's robot first, as it is the likeliest to
+        // lead to good results, and so to prune others.
+        let others = (0..self.resources.len()).rev().filter(|&r| r != objective);
+        for robot in std::iter::once(objective).chain(others) {
+            if state.robots[robot] >= search.max_robots[robot] {
+                continue;
+            }
+            let Some(wait) = self
+                .time_to_robot(state, robot)
+                .filter(|wait| state.time + wait <= search.time_limit)
+            else {
+                continue;
+            };
+
+            // Build the robot, and undo that again once we've seen
+            // where it leads.
+            state.time += wait;
+            for ((resource, n), cost) in state
+                .resources
+                .iter_mut()
+                .zip(&state.robots)
+                .zip(&self.costs[robot])
+            {
+                *resource = *resource + n * wait - cost;
+            }
+            state.robots[robot] += 1;
+            search.builds.push((state.time, robot));
+
+            // What we end up with if we build nothing else.
+            let collected = state.resources[objective]
+                + state.robots[objective] * (search.time_limit - state.time);
+            if collected > search.most_collected {
+                search.most_collected = collected;
+                search.best.clone_from(&search.builds);
+            }
+            if state.time < search.time_limit {
+                self.find_most(state, search);
+            }
 
-            if fork.time < time_limit {
-                self.find_max_geodes(fork, time_limit, most_geodes, earliest_geode);
+            search.builds.pop();
+            state.robots[robot] -= 1;
+            for ((resource, n), cost) in state
+                .resources
+                .iter_mut()
+                .zip(&state.robots)
+                .zip(&self.costs[robot])
+            {
+                *resource = *resource + cost - n * wait;
             }
+            state.time -= wait;
         }
-        if state.ore_robots < self.max_ore_need()
-            && state.time.saturating_add(time_to_ore) <= time_limit
-        {
-            let mut fork = state;
-            fork.time += time_to_ore;
+    }
+}
 
-            fork.ore += fork.ore_robots * time_to_ore;
-            fork.clay += fork.clay_robots * time_to_ore;
-            fork.obsidian += fork.obsidian_robots * time_to_ore;
-            fork.geodes += fork.geode_robots * time_to_ore;
+/// What happens in one minute of a schedule.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Minute {
+    pub minute: u32,
+    /// The robot that starts being built, if any.
+    pub build: Option<usize>,
+    /// The amount of each resource at the end of the minute.
+    pub resources: Vec<u32>,
+    /// The robots of each kind at the end of the minute.
+    pub robots: Vec<u32>,
+}
 
-            fork.ore_robots += 1;
-            fork.ore -= self.ore_robot_cost;
+/// A plan for which robots to build, minute by minute.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Schedule {
+    resources: Vec<String>,
+    costs: Vec<Vec<u32>>,
+    objective: usize,
+    pub minutes: Vec<Minute>,
+}
 
-            *most_geodes = (*most_geodes).max(fork.geodes);
+impl Schedule {
+    /// Returns how much of the objective is collected by the end.
+    pub fn collected(&self) -> u32 {
+        self.minutes
+            .last()
+            .map_or(0, |m| m.resources[self.objective])
+    }
+}
 
-            if fork.time < time_limit {
-                self.find_max_geodes(fork, time_limit, most_geodes, earliest_geode);
+/// Narrates the schedule, the way the puzzle describes one.
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Geodes are cracked rather than collected.
+        let name = |robot: usize| match self.resources[robot].as_str() {
+            "geode" => "geode-cracking".to_string(),
+            resource => format!("{resource}-collecting"),
+        };
+        for (i, minute) in self.minutes.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "== Minute {} ==", minute.minute)?;
+            if let Some(robot) = minute.build {
+                let cost = self.costs[robot]
+                    .iter()
+                    .zip(&self.resources)
+                    .filter(|(&n, _)| n > 0)
+                    .map(|(n, resource)| format!("{n} {resource}"))
+                    .join(" and ");
+                let name = name(robot);
+                let article = if name.starts_with(['a', 'e', 'i', 'o', 'u']) {
+                    "an"
+                } else {
+                    "a"
+                };
+                writeln!(f, "Spend {cost} to start building {article} {name} robot.")?;
+            }
+            for (robot, resource) in self.resources.iter().enumerate() {
+                // Robots collect from the minute after they are built.
+                let mut working = minute.robots[robot];
+                if minute.build == Some(robot) {
+                    working -= 1;
+                }
+                if working == 0 {
+                    continue;
+                }
+                let total = minute.resources[robot];
+                let s = if working == 1 { "" } else { "s" };
+                if resource == "geode" {
+                    let verb = if working == 1 { "cracks" } else { "crack" };
+                    let total_s = if total == 1 { "" } else { "s" };
+                    writeln!(
+                        f,
+                        "{working} {} robot{s} {verb} {working} geode{s}; \
+                         you now have {total} open geode{total_s}.",
+                        name(robot)
+                    )?;
+                } else {
+                    let verb = if working == 1 { "collects" } else { "collect" };
+                    writeln!(
+                        f,
+                        "{working} {} robot{s} {verb} {working} {resource}; \
+                         you now have {total} {resource}.",
+                        name(robot)
+                    )?;
+                }
+            }
+            if let Some(robot) = minute.build {
+                writeln!(
+                    f,
+                    "The new {} robot is ready; you now have {} of them.",
+                    name(robot),
+                    minute.robots[robot]
+                )?;
             }
         }
+        Ok(())
     }
 }
 
-/// Divides and rounds up. Actual div_ceil is still unstable, and I
-/// don't want to use a nightly version, so here's a specialised port.
-fn div_ceil(lhs: u8, rhs: u8) -> u8 {
-    assert!(rhs != 0);
-    lhs / rhs + (lhs % rhs).min(1)
+/// A robot's name, and what it costs as amounts of named resources.
+type Recipe<'a> = (&'a str, Vec<(u32, &'a str)>);
+
+fn parse_blueprint(i: &str) -> IResult<&str, (u8, Vec<Recipe<'_>>)> {
+    tuple((
+        delimited(tag("Blueprint "), u8, char(':')),
+        many1(preceded(tag(" Each "), parse_recipe)),
+    ))(i)
 }
 
-fn parse_blueprint(i: &str) -> IResult<&str, Blueprint> {
-    let (i, _) = tag("Blueprint ")(i)?;
-    let (i, id) = u8(i)?;
-    let (i, _) = tag(": Each ore robot costs ")(i)?;
-    let (i, ore_robot_cost) = u8(i)?;
-    let (i, _) = tag(" ore. Each clay robot costs ")(i)?;
-    let (i, clay_robot_cost) = u8(i)?;
-    let (i, _) = tag(" ore. Each obsidian robot costs ")(i)?;
-    let (i, obsidian_robot_ore_cost) = u8(i)?;
-    let (i, _) = tag(" ore and ")(i)?;
-    let (i, obsidian_robot_clay_cost) = u8(i)?;
-    let (i, _) = tag(" clay. Each geode robot costs ")(i)?;
-    let (i, geode_robot_ore_cost) = u8(i)?;
-    let (i, _) = tag(" ore and ")(i)?;
-    let (i, geode_robot_obsidian_cost) = u8(i)?;
-    let (i, _) = tag(" obsidian.")(i)?;
-    Ok((
-        i,
-        Blueprint {
-            id,
-            ore_robot_cost,
-            clay_robot_cost,
-            obsidian_robot_cost: (obsidian_robot_ore_cost, obsidian_robot_clay_cost),
-            geode_robot_cost: (geode_robot_ore_cost, geode_robot_obsidian_cost),
-        },
-    ))
+fn parse_recipe(i: &str) -> IResult<&str, Recipe<'_>> {
+    separated_pair(
+        alpha1,
+        tag(" robot costs "),
+        terminated(
+            separated_list1(tag(" and "), separated_pair(u32, char(' '), alpha1)),
+            char('.'),
+        ),
+    )(i)
 }
 
 #[cfg(test)]
@@ -300,12 +450,63 @@ Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsid
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 33);
+        assert_eq!(Day19::part1(&Day19::parse(INPUT).unwrap()), Ok(33));
     }
 
     // Slow
     // #[test]
     // fn part2_example() {
-    //     assert_eq!(part2(INPUT), 56 * 62);
+    //     assert_eq!(Day19::part2(&Day19::parse(INPUT).unwrap()), Ok(56 * 62));
     // }
+
+    #[test]
+    fn plan() {
+        let blueprints = Day19::parse(INPUT).unwrap();
+        let schedule = blueprints[0].plan(24);
+        assert_eq!(schedule.collected(), 9);
+        assert_eq!(schedule.minutes.len(), 24);
+        assert_eq!(schedule.minutes[23].robots[3], 2);
+        let narrative = schedule.to_string();
+        assert!(narrative.starts_with(
+            "== Minute 1 ==
+1 ore-collecting robot collects 1 ore; you now have 1 ore.
+
+== Minute 2 ==
+1 ore-collecting robot collects 1 ore; you now have 2 ore.
+
+== Minute 3 ==
+Spend 2 ore to start building a clay-collecting robot.
+1 ore-collecting robot collects 1 ore; you now have 1 ore.
+The new clay-collecting robot is ready; you now have 1 of them.
+"
+        ));
+        assert!(narrative.ends_with("robots crack 2 geodes; you now have 9 open geodes.\n"));
+    }
+
+    #[test]
+    fn other_recipes() {
+        let blueprints = Day19::parse(
+            "Blueprint 7: Each wood robot costs 1 wood. Each stone robot costs 2 wood.",
+        )
+        .unwrap();
+        let blueprint = &blueprints[0];
+        assert_eq!(blueprint.resources, ["wood", "stone"]);
+        assert_eq!(blueprint.objective, 1);
+        assert_eq!(blueprint.most_collected(6), 4);
+        // Far beyond what fits in a u8.
+        assert_eq!(blueprint.most_collected(50), 1081);
+    }
+
+    #[test]
+    fn invalid_recipes() {
+        let error = |input| Day19::parse(input).unwrap_err().to_string();
+        assert_eq!(
+            error("Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 gold."),
+            "line 1, column 1: no robot collects gold"
+        );
+        assert_eq!(
+            error("Blueprint 1: Each ore robot costs 4 ore. Each ore robot costs 2 ore."),
+            "line 1, column 1: duplicate ore robot"
+        );
+    }
 }
@@ -1,70 +1,202 @@
-use itertools::Itertools;
+use crate::error::parse_lines;
+use crate::{Error, Result, Solution};
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/20.txt");
-    format!("{}\n{}", part1(input), part2(input))
-}
+pub const INPUT: &str = include_str!("../inputs/20.txt");
+
+pub struct Day20;
 
-fn part1(input: &str) -> i64 {
-    let mut nums: Vec<(usize, i64)> = input
-        .lines()
-        .map(|l| l.parse().expect("failed to parse file"))
-        .enumerate()
-        .collect();
+impl Solution for Day20 {
+    /// The encrypted file.
+    type Input = Vec<i64>;
+    type Part1 = i64;
+    type Part2 = i64;
 
-    mix(&mut nums);
+    const DAY: u8 = 20;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_lines(input, |l| {
+            l.parse()
+                .map_err(|_| Error::new(format!("invalid number {l:?}")))
+        })
+    }
 
-    let zero_position = nums.iter().position(|(_, n)| *n == 0).unwrap();
-    let a = nums[(zero_position + 1000) % nums.len()].1;
-    let b = nums[(zero_position + 2000) % nums.len()].1;
-    let c = nums[(zero_position + 3000) % nums.len()].1;
-    a + b + c
+    fn part1(file: &Self::Input) -> Result<i64> {
+        grove_sum(&decrypt(file, 1, 1, 0, &GROVE_COORDINATES)?)
+    }
+
+    fn part2(file: &Self::Input) -> Result<i64> {
+        grove_sum(&decrypt(file, 811589153, 10, 0, &GROVE_COORDINATES)?)
+    }
 }
 
-fn part2(input: &str) -> i64 {
-    let decryption_key = 811589153;
-    let mut nums: Vec<(usize, i64)> = input
-        .lines()
-        .map(|l| l.parse().expect("failed to parse file"))
-        .map(|n: i64| n * decryption_key)
-        .enumerate()
-        .collect();
+/// Adds up the grove coordinates.
+fn grove_sum(coordinates: &[i64]) -> Result<i64> {
+    coordinates
+        .iter()
+        .try_fold(0i64, |total, &n| total.checked_add(n))
+        .ok_or_else(|| Error::new("grove coordinates overflow when added up"))
+}
+
+/// The positions after the zero that make up the grove coordinates.
+pub const GROVE_COORDINATES: [usize; 3] = [1000, 2000, 3000];
 
-    for _ in 0..10 {
-        mix(&mut nums);
+/// Multiplies every number in the file by a key, mixes it a number of
+/// times, and returns the numbers at each offset after the anchor,
+/// which has to be in the file exactly once.
+pub fn decrypt(
+    file: &[i64],
+    key: i64,
+    rounds: usize,
+    anchor: i64,
+    offsets: &[usize],
+) -> Result<Vec<i64>> {
+    match file.iter().filter(|&&n| n == anchor).count() {
+        0 => return Err(Error::new(format!("no {anchor} in file"))),
+        1 => (),
+        n => return Err(Error::new(format!("{anchor} is in the file {n} times"))),
     }
+    let values = file
+        .iter()
+        .map(|n| {
+            n.checked_mul(key)
+                .ok_or_else(|| Error::new(format!("{n} overflows when multiplied by the key")))
+        })
+        .collect::<Result<_>>()?;
+    let mut mixer = CircularMixer::new(values);
+    mixer.mix(rounds);
+    // The anchor has been multiplied by the key as well.
+    let index = file.iter().position(|&n| n == anchor).unwrap();
+    Ok(offsets
+        .iter()
+        .map(|k| mixer.nth_after_index(index, *k))
+        .collect())
+}
 
-    let zero_position = nums.iter().position(|(_, n)| *n == 0).unwrap();
-    let a = nums[(zero_position + 1000) % nums.len()].1;
-    let b = nums[(zero_position + 2000) % nums.len()].1;
-    let c = nums[(zero_position + 3000) % nums.len()].1;
-    a + b + c
+/// A circular list of numbers, mixed by moving each number as many
+/// places along as its value, in the order they were first listed.
+///
+/// The list is kept in blocks of about √n numbers, so that finding
+/// and moving a number takes O(√n).
+#[derive(Clone, Debug)]
+pub struct CircularMixer {
+    /// The numbers in the order they were first listed.
+    values: Vec<i64>,
+    /// The indices of the numbers, in their current order.
+    blocks: Vec<Vec<usize>>,
+    /// The block each number is in, by index.
+    block_of: Vec<usize>,
+    block_size: usize,
 }
 
-/// Mixes nums once, according to the instructions.
-fn mix(nums: &mut Vec<(usize, i64)>) {
-    for idx in 0..nums.len() {
-        let (current_index, (order, num)) = nums.iter().find_position(|(i, _)| *i == idx).unwrap();
-        // Copy values to avoid ownership issues.
-        let num = *num;
-        let order = *order;
-
-        // Just skip numbers that don't need to move.
-        if num == 0 {
-            continue;
+impl CircularMixer {
+    pub fn new(values: Vec<i64>) -> Self {
+        let block_size = (values.len() as f64).sqrt().ceil().max(1.0) as usize;
+        let mut mixer = Self {
+            blocks: vec![(0..values.len()).collect()],
+            block_of: vec![0; values.len()],
+            values,
+            block_size,
+        };
+        mixer.rebalance();
+        mixer
+    }
+
+    /// Mixes the numbers a number of times.
+    pub fn mix(&mut self, rounds: usize) {
+        // A number moving all the way round passes the n - 1 others.
+        let others = self.values.len() as i64 - 1;
+        if others < 1 {
+            return;
         }
+        for _ in 0..rounds {
+            for idx in 0..self.values.len() {
+                let position = self.remove(idx);
+                // Reduce the value first, so that adding can't overflow.
+                let steps = self.values[idx].rem_euclid(others);
+                let position = (position as i64 + steps).rem_euclid(others);
+                self.insert(position as usize, idx);
+            }
+        }
+    }
+
+    /// Returns the number k places after the first number listed with
+    /// a value, or None if there is no such number.
+    pub fn nth_after(&self, value: i64, k: usize) -> Option<i64> {
+        let idx = self.values.iter().position(|&v| v == value)?;
+        Some(self.nth_after_index(idx, k))
+    }
 
-        nums.remove(current_index);
+    /// Returns the number k places after the one listed at an index.
+    fn nth_after_index(&self, idx: usize, k: usize) -> i64 {
+        self.nth((self.position(idx) + k) % self.values.len())
+            .unwrap()
+    }
 
-        let raw_new_index = current_index as i64 + num;
-        let new_index: i64 = if num > 0 {
-            raw_new_index % nums.len() as i64
-        } else if raw_new_index < 0 {
-            nums.len() as i64 - (raw_new_index.abs() % nums.len() as i64)
-        } else {
-            raw_new_index
-        };
-        nums.insert(new_index as usize, (order, num));
+    /// Returns the numbers in their current order, starting with the
+    /// first number listed with a value, or None if there is no such
+    /// number.
+    pub fn order_from(&self, value: i64) -> Option<Vec<i64>> {
+        (0..self.values.len())
+            .map(|k| self.nth_after(value, k))
+            .collect()
+    }
+
+    /// Returns the number at a position.
+    fn nth(&self, mut position: usize) -> Option<i64> {
+        for block in &self.blocks {
+            if position < block.len() {
+                return Some(self.values[block[position]]);
+            }
+            position -= block.len();
+        }
+        None
+    }
+
+    /// Returns the current position of a number by its index.
+    fn position(&self, idx: usize) -> usize {
+        let block = self.block_of[idx];
+        let before: usize = self.blocks[..block].iter().map(Vec::len).sum();
+        before + self.blocks[block].iter().position(|&i| i == idx).unwrap()
+    }
+
+    /// Takes a number out of the list by its index, and returns the
+    /// position it was at.
+    fn remove(&mut self, idx: usize) -> usize {
+        let position = self.position(idx);
+        let block = &mut self.blocks[self.block_of[idx]];
+        block.retain(|&i| i != idx);
+        position
+    }
+
+    /// Puts a number back into the list at a position.
+    fn insert(&mut self, mut position: usize, idx: usize) {
+        let last = self.blocks.len() - 1;
+        for (b, block) in self.blocks.iter_mut().enumerate() {
+            if position <= block.len() && (position < block.len() || b == last) {
+                block.insert(position, idx);
+                self.block_of[idx] = b;
+                if block.len() > 2 * self.block_size {
+                    self.rebalance();
+                }
+                return;
+            }
+            position -= block.len();
+        }
+    }
+
+    /// Splits the list into blocks of equal size again.
+    fn rebalance(&mut self) {
+        let order: Vec<usize> = self.blocks.concat();
+        self.blocks = order
+            .chunks(self.block_size)
+            .map(<[usize]>::to_vec)
+            .collect();
+        if self.blocks.is_empty() {
+            self.blocks.push(vec![]);
+        }
+        for (b, block) in self.blocks.iter().enumerate() {
+            block.iter().for_each(|&idx| self.block_of[idx] = b);
+        }
     }
 }
 
@@ -83,11 +215,123 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 3);
+        assert_eq!(Day20::part1(&Day20::parse(INPUT).unwrap()), Ok(3));
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(INPUT), 1623178306);
+        assert_eq!(Day20::part2(&Day20::parse(INPUT).unwrap()), Ok(1623178306));
+    }
+
+    /// Mixes the straightforward way, moving numbers around in a
+    /// list.
+    fn mix_slowly(values: &[i64], rounds: usize) -> Vec<i64> {
+        let mut nums: Vec<(usize, i64)> = values.iter().copied().enumerate().collect();
+        let others = values.len() as i64 - 1;
+        if others < 1 {
+            return values.to_vec();
+        }
+        for _ in 0..rounds {
+            for idx in 0..values.len() {
+                let position = nums.iter().position(|(i, _)| *i == idx).unwrap();
+                let num = nums.remove(position);
+                let position = (position as i64 + num.1.rem_euclid(others)).rem_euclid(others);
+                nums.insert(position as usize, num);
+            }
+        }
+        let zero = nums.iter().position(|(_, n)| *n == 0).unwrap();
+        nums.rotate_left(zero);
+        nums.into_iter().map(|(_, n)| n).collect()
+    }
+
+    #[test]
+    fn overflowing_sums() {
+        let overflow = Err(Error::new("grove coordinates overflow when added up"));
+        assert_eq!(Day20::part1(&vec![0, i64::MAX, 1]), overflow);
+        // Fine on its own once multiplied by the key, but not added up.
+        let file = vec![0, 11_000_000_000, 11_000_000_000];
+        assert!(decrypt(&file, 811589153, 10, 0, &GROVE_COORDINATES).is_ok());
+        assert_eq!(Day20::part2(&file), overflow);
+    }
+
+    #[test]
+    fn decryption() {
+        let file = Day20::parse(INPUT).unwrap();
+        assert_eq!(
+            decrypt(&file, 1, 1, 0, &GROVE_COORDINATES),
+            Ok(vec![4, -3, 2])
+        );
+        assert_eq!(
+            decrypt(&file, 811589153, 10, 0, &GROVE_COORDINATES),
+            Ok(vec![811589153, 2434767459, -1623178306])
+        );
+        assert_eq!(
+            decrypt(&file, 1, 0, 3, &[0, 1, 7, 8]),
+            Ok(vec![3, -2, 3, -2])
+        );
+        assert_eq!(
+            decrypt(&file, 1, 1, 5, &[1]),
+            Err(Error::new("no 5 in file"))
+        );
+        assert_eq!(
+            decrypt(&[1, 0, 2, 0], 1, 1, 0, &[1]),
+            Err(Error::new("0 is in the file 2 times"))
+        );
+        assert_eq!(
+            decrypt(&[0, 1 << 40], 1 << 30, 1, 0, &[1]),
+            Err(Error::new(
+                "1099511627776 overflows when multiplied by the key"
+            ))
+        );
+
+        // Duplicates of other numbers are moved one by one, in the
+        // order they are listed.
+        let file = [3, 1, 3, 0, 1, -2, -2];
+        assert_eq!(
+            decrypt(&file, 1, 2, 0, &[0, 1, 2, 3, 4, 5, 6]),
+            Ok(mix_slowly(&file, 2))
+        );
+    }
+
+    #[test]
+    fn mixer() {
+        let mut mixer = CircularMixer::new(Day20::parse(INPUT).unwrap());
+        mixer.mix(1);
+        assert_eq!(mixer.order_from(0), Some(vec![0, 3, -2, 1, 2, -3, 4]));
+        assert_eq!(mixer.nth_after(-2, 2), Some(2));
+        assert_eq!(mixer.nth_after(5, 0), None);
+
+        // Numbers at the ends of the range don't overflow.
+        let values = vec![0, i64::MAX, 1, i64::MIN, -1];
+        let mut mixer = CircularMixer::new(values.clone());
+        mixer.mix(2);
+        assert_eq!(mixer.order_from(0).unwrap(), mix_slowly(&values, 2));
+        let mut mixer = CircularMixer::new(vec![0, i64::MAX, 1]);
+        mixer.mix(1);
+        assert_eq!(mixer.order_from(0), Some(vec![0, i64::MAX, 1]));
+
+        // Lots of duplicates and numbers much larger than the list.
+        let mut seed = 1u64;
+        for len in [1, 2, 3, 10, 100, 1000] {
+            let values: Vec<i64> = (0..len)
+                .map(|i| {
+                    seed = seed
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    match i {
+                        0 => 0,
+                        _ if i % 3 == 0 => (seed >> 60) as i64 - 8,
+                        _ => (seed >> 20) as i64 - (1 << 43),
+                    }
+                })
+                .collect();
+            let mut mixer = CircularMixer::new(values.clone());
+            mixer.mix(3);
+            assert_eq!(
+                mixer.order_from(0).unwrap(),
+                mix_slowly(&values, 3),
+                "{len}"
+            );
+        }
     }
 }
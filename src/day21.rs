@@ -1,137 +1,97 @@
-use fxhash::FxHashMap;
+use std::cmp::Ordering;
+use std::fmt;
+
+use fxhash::{FxHashMap, FxHashSet};
 use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::{alpha1, one_of, u64},
-    combinator::{all_consuming, map},
+    combinator::map,
     sequence::tuple,
     IResult,
 };
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/21.txt");
-    format!("{}\n{}", part1(input), part2(input))
-}
+use crate::error::{nom, parse_lines};
+use crate::{Error, Result, Solution};
 
-fn part1(input: &str) -> u64 {
-    let monkeys: FxHashMap<String, Monkey> = input
-        .lines()
-        .map(|l| {
-            all_consuming(parse_monkey)(l)
-                .expect("failed to parse monkey")
-                .1
-        })
-        .map(|m| (m.name.clone(), m))
-        .collect();
-    monkeys.get("root").unwrap().resolve(&monkeys)
+pub const INPUT: &str = include_str!("../inputs/21.txt");
+
+pub struct Day21;
+
+impl Solution for Day21 {
+    /// All monkeys by name.
+    type Input = FxHashMap<String, Monkey>;
+    type Part1 = i64;
+    type Part2 = u64;
+
+    const DAY: u8 = 21;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        let monkeys = parse_lines(input, nom(parse_monkey, "monkey"))?;
+        check_tree(&monkeys)?;
+        Ok(monkeys.into_iter().map(|m| (m.name.clone(), m)).collect())
+    }
+
+    fn part1(monkeys: &Self::Input) -> Result<i64> {
+        monkey("root", monkeys)?.resolve(monkeys)
+    }
+
+    fn part2(monkeys: &Self::Input) -> Result<u64> {
+        let (lhs, rhs) = monkey("root", monkeys)?
+            .number
+            .operands()
+            .ok_or_else(|| Error::new("root does not compare two monkeys"))?;
+        let answer = Equation::new("humn", Target::Equal(lhs, rhs), monkeys)?.solve()?;
+        let answer = answer
+            .integer()
+            .ok_or_else(|| Error::new(format!("answer {answer} is not an integer")))?;
+        u64::try_from(answer).map_err(|_| Error::new(format!("answer {answer} is negative")))
+    }
 }
 
-fn part2(input: &str) -> u64 {
-    let monkeys: FxHashMap<String, Monkey> = input
-        .lines()
-        .map(|l| {
-            all_consuming(parse_monkey)(l)
-                .expect("failed to parse monkey")
-                .1
-        })
-        .map(|m| (m.name.clone(), m))
-        .collect();
-    monkeys.get("humn").unwrap().resolve_inverse(&monkeys)
+/// Returns the monkey with a given name.
+fn monkey<'a>(name: &str, monkeys: &'a FxHashMap<String, Monkey>) -> Result<&'a Monkey> {
+    monkeys
+        .get(name)
+        .ok_or_else(|| Error::new(format!("unknown monkey {name}")))
 }
 
 #[derive(Clone, Debug)]
-struct Monkey {
-    name: String,
-    number: Number,
+pub struct Monkey {
+    pub name: String,
+    pub number: Number,
 }
 
 impl Monkey {
-    /// Returns the integer number of this monkey, resolving other
-    /// monkeys if required.
-    fn resolve(&self, monkeys: &FxHashMap<String, Self>) -> u64 {
-        match &self.number {
-            Number::Atom(n) => *n,
-            Number::Add(a, b) => {
-                let lhs = monkeys.get(a).unwrap().resolve(monkeys);
-                let rhs = monkeys.get(b).unwrap().resolve(monkeys);
-                lhs + rhs
-            }
-            Number::Subtract(a, b) => {
-                let lhs = monkeys.get(a).unwrap().resolve(monkeys);
-                let rhs = monkeys.get(b).unwrap().resolve(monkeys);
-                lhs - rhs
-            }
-            Number::Multiply(a, b) => {
-                let lhs = monkeys.get(a).unwrap().resolve(monkeys);
-                let rhs = monkeys.get(b).unwrap().resolve(monkeys);
-                lhs * rhs
-            }
-            Number::Divide(a, b) => {
-                let lhs = monkeys.get(a).unwrap().resolve(monkeys);
-                let rhs = monkeys.get(b).unwrap().resolve(monkeys);
-                lhs / rhs
-            }
-        }
-    }
-
-    /// Returns the inverse solution of this monkey, resolving "up the
-    /// tree."
-    fn resolve_inverse(&self, monkeys: &FxHashMap<String, Self>) -> u64 {
-        // Find the monkey above us that uses our result.
-        let used_by = monkeys
-            .values()
-            .find(|m| match &m.number {
-                Number::Add(a, b) => a == &self.name || b == &self.name,
-                Number::Subtract(a, b) => a == &self.name || b == &self.name,
-                Number::Multiply(a, b) => a == &self.name || b == &self.name,
-                Number::Divide(a, b) => a == &self.name || b == &self.name,
-                Number::Atom(_) => false,
-            })
-            .unwrap();
-
-        // Find out on which side of the next monkey up we are.
-        let (lhs, rhs) = match &used_by.number {
-            Number::Add(a, b) => (a, b),
-            Number::Subtract(a, b) => (a, b),
-            Number::Multiply(a, b) => (a, b),
-            Number::Divide(a, b) => (a, b),
-            Number::Atom(_) => unreachable!(),
+    /// Returns the number of this monkey, resolving other monkeys if
+    /// required. Fails on overflow or division by zero, naming the
+    /// monkey it happens at.
+    pub fn resolve<T: Integer>(&self, monkeys: &FxHashMap<String, Self>) -> Result<T> {
+        let overflow = || Error::new(format!("{} overflows", self.name));
+        let Some((a, b)) = self.number.operands() else {
+            let Number::Atom(n) = self.number else {
+                unreachable!()
+            };
+            return T::from_u64(n).ok_or_else(overflow);
         };
-
-        if used_by.name == "root" {
-            // Switch to regular resolution down the other side once
-            // we hit root.
-            if lhs == &self.name {
-                monkeys.get(rhs).unwrap().resolve(monkeys)
-            } else {
-                monkeys.get(lhs).unwrap().resolve(monkeys)
-            }
-        } else if lhs == &self.name {
-            // Resolve downwards on the right hand side branch.
-            let rhs = monkeys.get(rhs).unwrap().resolve(monkeys);
-            match &used_by.number {
-                Number::Add(_, _) => used_by.resolve_inverse(monkeys) - rhs,
-                Number::Subtract(_, _) => used_by.resolve_inverse(monkeys) + rhs,
-                Number::Multiply(_, _) => used_by.resolve_inverse(monkeys) / rhs,
-                Number::Divide(_, _) => used_by.resolve_inverse(monkeys) * rhs,
-                Number::Atom(_) => unreachable!(),
-            }
-        } else {
-            // Resolve downwards the left hand side branch.
-            let lhs = monkeys.get(lhs).unwrap().resolve(monkeys);
-            match &used_by.number {
-                Number::Add(_, _) => used_by.resolve_inverse(monkeys) - lhs,
-                Number::Subtract(_, _) => lhs - used_by.resolve_inverse(monkeys),
-                Number::Multiply(_, _) => used_by.resolve_inverse(monkeys) / lhs,
-                Number::Divide(_, _) => lhs / used_by.resolve_inverse(monkeys),
-                Number::Atom(_) => unreachable!(),
+        let lhs: T = monkey(a, monkeys)?.resolve(monkeys)?;
+        let rhs: T = monkey(b, monkeys)?.resolve(monkeys)?;
+        match &self.number {
+            Number::Add(_, _) => lhs.checked_add(&rhs),
+            Number::Subtract(_, _) => lhs.checked_sub(&rhs),
+            Number::Multiply(_, _) => lhs.checked_mul(&rhs),
+            Number::Divide(_, _) if rhs.is_zero() => {
+                return Err(Error::new(format!("{} divides by zero", self.name)))
             }
+            Number::Divide(_, _) => lhs.checked_div(&rhs),
+            Number::Atom(_) => unreachable!(),
         }
+        .ok_or_else(overflow)
     }
 }
 
 #[derive(Clone, Debug)]
-enum Number {
+pub enum Number {
     Atom(u64),
     Add(String, String),
     Subtract(String, String),
@@ -139,6 +99,573 @@ enum Number {
     Divide(String, String),
 }
 
+impl Number {
+    /// Returns the names of the monkeys this number is worked out
+    /// from, if any.
+    pub fn operands(&self) -> Option<(&str, &str)> {
+        match self {
+            Self::Atom(_) => None,
+            Self::Add(a, b) | Self::Subtract(a, b) | Self::Multiply(a, b) | Self::Divide(a, b) => {
+                Some((a, b))
+            }
+        }
+    }
+}
+
+/// Checks that the monkeys form trees, with no monkey used by more
+/// than one other, and none depending on itself.
+fn check_tree(monkeys: &[Monkey]) -> Result<()> {
+    let mut used_by = FxHashMap::default();
+    for m in monkeys {
+        for operand in m.number.operands().into_iter().flat_map(|(a, b)| [a, b]) {
+            if let Some(other) = used_by.insert(operand, m.name.as_str()) {
+                return Err(Error::new(format!(
+                    "{operand} is used by both {other} and {}",
+                    m.name
+                )));
+            }
+        }
+    }
+
+    // Every monkey that can't be reached from one used by nobody is
+    // in a cycle, or depends on one.
+    let by_name: FxHashMap<_, _> = monkeys.iter().map(|m| (m.name.as_str(), m)).collect();
+    let mut reached = FxHashSet::default();
+    let mut todo: Vec<_> = monkeys
+        .iter()
+        .map(|m| m.name.as_str())
+        .filter(|name| !used_by.contains_key(name))
+        .collect();
+    while let Some(name) = todo.pop() {
+        reached.insert(name);
+        if let Some((a, b)) = by_name.get(name).and_then(|m| m.number.operands()) {
+            todo.extend([a, b]);
+        }
+    }
+    if let Some(m) = monkeys.iter().find(|m| !reached.contains(m.name.as_str())) {
+        // Walk up until we come round.
+        let mut seen = FxHashSet::default();
+        let mut name = m.name.as_str();
+        while seen.insert(name) {
+            name = used_by[name];
+        }
+        return Err(Error::new(format!("{name} depends on itself")));
+    }
+    Ok(())
+}
+
+/// An integer type to work out the monkeys' numbers with, which fails
+/// instead of overflowing.
+pub trait Integer: Clone + fmt::Display + Sized {
+    fn from_u64(n: u64) -> Option<Self>;
+    fn is_zero(&self) -> bool;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    /// Divides, rounding towards zero.
+    fn checked_div(&self, other: &Self) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {$(
+        impl Integer for $t {
+            fn from_u64(n: u64) -> Option<Self> {
+                n.try_into().ok()
+            }
+
+            fn is_zero(&self) -> bool {
+                *self == 0
+            }
+
+            fn checked_add(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *other)
+            }
+
+            fn checked_sub(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_sub(*self, *other)
+            }
+
+            fn checked_mul(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_mul(*self, *other)
+            }
+
+            fn checked_div(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_div(*self, *other)
+            }
+        }
+    )*};
+}
+
+impl_integer!(i64, i128);
+
+/// An integer of any size, which never overflows.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BigInt {
+    negative: bool,
+    /// The digits of the magnitude in base 2^32, least significant
+    /// first, without leading zeros.
+    digits: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Self {
+            negative: negative && !digits.is_empty(),
+            digits,
+        }
+    }
+
+    fn negate(&self) -> Self {
+        Self::new(!self.negative, self.digits.clone())
+    }
+}
+
+/// Compares two magnitudes.
+fn cmp_digits(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_digits(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut digits = vec![];
+    let mut carry = 0;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        digits.push(sum as u32);
+        carry = sum >> 32;
+    }
+    digits.push(carry as u32);
+    digits
+}
+
+/// Subtracts a smaller magnitude from a larger one.
+fn sub_digits(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut digits = vec![];
+    let mut borrow = 0;
+    for (i, &digit) in a.iter().enumerate() {
+        let difference = digit as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        digits.push(difference.rem_euclid(1 << 32) as u32);
+        borrow = (difference < 0) as i64;
+    }
+    digits
+}
+
+/// Divides magnitudes one bit at a time, returning the quotient and
+/// remainder.
+fn div_digits(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut quotient = vec![0; a.len()];
+    let mut remainder: Vec<u32> = vec![];
+    for bit in (0..a.len() * 32).rev() {
+        // Shift the next bit of a into the remainder.
+        let mut carry = a[bit / 32] >> (bit % 32) & 1;
+        for digit in remainder.iter_mut() {
+            let next = *digit >> 31;
+            *digit = *digit << 1 | carry;
+            carry = next;
+        }
+        if carry != 0 {
+            remainder.push(carry);
+        }
+        if cmp_digits(&remainder, b) != Ordering::Less {
+            remainder = sub_digits(&remainder, b);
+            while remainder.last() == Some(&0) {
+                remainder.pop();
+            }
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+    (quotient, remainder)
+}
+
+impl Integer for BigInt {
+    fn from_u64(n: u64) -> Option<Self> {
+        Some(Self::new(false, vec![n as u32, (n >> 32) as u32]))
+    }
+
+    fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(if self.negative == other.negative {
+            Self::new(self.negative, add_digits(&self.digits, &other.digits))
+        } else if cmp_digits(&self.digits, &other.digits) == Ordering::Less {
+            Self::new(other.negative, sub_digits(&other.digits, &self.digits))
+        } else {
+            Self::new(self.negative, sub_digits(&self.digits, &other.digits))
+        })
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        self.checked_add(&other.negate())
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let mut digits = vec![0; self.digits.len() + other.digits.len()];
+        for (i, &a) in self.digits.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in other.digits.iter().enumerate() {
+                let product = a as u64 * b as u64 + digits[i + j] as u64 + carry;
+                digits[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            digits[i + other.digits.len()] = carry as u32;
+        }
+        Some(Self::new(self.negative != other.negative, digits))
+    }
+
+    fn checked_div(&self, other: &Self) -> Option<Self> {
+        if other.is_zero() {
+            return None;
+        }
+        let (quotient, _) = div_digits(&self.digits, &other.digits);
+        Some(Self::new(self.negative != other.negative, quotient))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // Split off nine decimal digits at a time.
+        let mut chunks = vec![];
+        let mut digits = self.digits.clone();
+        while !digits.is_empty() {
+            let (quotient, remainder) = div_digits(&digits, &[1_000_000_000]);
+            chunks.push(remainder.first().copied().unwrap_or(0));
+            digits = Self::new(false, quotient).digits;
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", chunks.pop().unwrap())?;
+        chunks
+            .iter()
+            .rev()
+            .try_for_each(|chunk| write!(f, "{chunk:09}"))
+    }
+}
+
+/// What the numbers the monkeys yell have to add up to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Target<'a> {
+    /// A monkey has to yell a given number.
+    Value(&'a str, Rational),
+    /// Two monkeys have to yell the same number.
+    Equal(&'a str, &'a str),
+}
+
+/// An equation in the number one monkey yells.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Equation {
+    pub lhs: Expr,
+    pub rhs: Expr,
+}
+
+impl Equation {
+    /// Returns the equation for the number of the unknown monkey that
+    /// meets the target, with everything else worked out.
+    pub fn new(unknown: &str, target: Target, monkeys: &FxHashMap<String, Monkey>) -> Result<Self> {
+        monkey(unknown, monkeys)?;
+        let (lhs, rhs) = match target {
+            Target::Value(name, value) => (Expr::new(name, unknown, monkeys)?, Expr::Atom(value)),
+            Target::Equal(a, b) => (
+                Expr::new(a, unknown, monkeys)?,
+                Expr::new(b, unknown, monkeys)?,
+            ),
+        };
+        Ok(Self {
+            lhs: lhs.reduce()?,
+            rhs: rhs.reduce()?,
+        })
+    }
+
+    /// Returns the number of the unknown monkey.
+    pub fn solve(&self) -> Result<Rational> {
+        solve(&self.lhs, &self.rhs)
+    }
+}
+
+impl fmt::Display for Equation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} = {}", self.lhs, self.rhs)
+    }
+}
+
+/// The number a monkey yells, as an expression of the numbers other
+/// monkeys yell, with one of them unknown.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Expr {
+    Atom(Rational),
+    Unknown,
+    Add(Box<Expr>, Box<Expr>),
+    Subtract(Box<Expr>, Box<Expr>),
+    Multiply(Box<Expr>, Box<Expr>),
+    Divide(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Returns the expression for a monkey, treating the number of
+    /// the unknown monkey as the unknown.
+    pub fn new(name: &str, unknown: &str, monkeys: &FxHashMap<String, Monkey>) -> Result<Self> {
+        if name == unknown {
+            return Ok(Self::Unknown);
+        }
+        let expr = |name| Expr::new(name, unknown, monkeys).map(Box::new);
+        Ok(match &monkey(name, monkeys)?.number {
+            Number::Atom(n) => Self::Atom(Rational::from(*n as i128)),
+            Number::Add(a, b) => Self::Add(expr(a)?, expr(b)?),
+            Number::Subtract(a, b) => Self::Subtract(expr(a)?, expr(b)?),
+            Number::Multiply(a, b) => Self::Multiply(expr(a)?, expr(b)?),
+            Number::Divide(a, b) => Self::Divide(expr(a)?, expr(b)?),
+        })
+    }
+
+    /// Reduces the expression to a linear function of the unknown,
+    /// failing if the unknown is multiplied by itself or divided by.
+    pub fn linear(&self) -> Result<Linear> {
+        let nonlinear = || Error::new("the unknown appears nonlinearly");
+        Ok(match self {
+            Self::Atom(n) => Linear::constant(*n),
+            Self::Unknown => Linear {
+                slope: Rational::from(1),
+                intercept: Rational::from(0),
+            },
+            Self::Add(a, b) => {
+                let (a, b) = (a.linear()?, b.linear()?);
+                Linear {
+                    slope: a.slope.checked_add(b.slope)?,
+                    intercept: a.intercept.checked_add(b.intercept)?,
+                }
+            }
+            Self::Subtract(a, b) => {
+                let (a, b) = (a.linear()?, b.linear()?);
+                Linear {
+                    slope: a.slope.checked_sub(b.slope)?,
+                    intercept: a.intercept.checked_sub(b.intercept)?,
+                }
+            }
+            Self::Multiply(a, b) => {
+                let (a, b) = (a.linear()?, b.linear()?);
+                let (factor, other) = match (a.value(), b.value()) {
+                    (Some(factor), _) => (factor, b),
+                    (_, Some(factor)) => (factor, a),
+                    _ => return Err(nonlinear()),
+                };
+                Linear {
+                    slope: other.slope.checked_mul(factor)?,
+                    intercept: other.intercept.checked_mul(factor)?,
+                }
+            }
+            Self::Divide(a, b) => {
+                let (a, b) = (a.linear()?, b.linear()?);
+                let divisor = b.value().ok_or_else(nonlinear)?;
+                Linear {
+                    slope: a.slope.checked_div(divisor)?,
+                    intercept: a.intercept.checked_div(divisor)?,
+                }
+            }
+        })
+    }
+
+    /// Returns the expression with every part that does not depend on
+    /// the unknown worked out.
+    pub fn reduce(&self) -> Result<Self> {
+        let reduced = match self {
+            Self::Atom(_) | Self::Unknown => return Ok(self.clone()),
+            Self::Add(a, b) => Self::Add(Box::new(a.reduce()?), Box::new(b.reduce()?)),
+            Self::Subtract(a, b) => Self::Subtract(Box::new(a.reduce()?), Box::new(b.reduce()?)),
+            Self::Multiply(a, b) => Self::Multiply(Box::new(a.reduce()?), Box::new(b.reduce()?)),
+            Self::Divide(a, b) => Self::Divide(Box::new(a.reduce()?), Box::new(b.reduce()?)),
+        };
+        Ok(match reduced.operands() {
+            Some((Self::Atom(_), Self::Atom(_))) => Self::Atom(reduced.linear()?.intercept),
+            _ => reduced,
+        })
+    }
+
+    fn operands(&self) -> Option<(&Self, &Self)> {
+        match self {
+            Self::Atom(_) | Self::Unknown => None,
+            Self::Add(a, b) | Self::Subtract(a, b) | Self::Multiply(a, b) | Self::Divide(a, b) => {
+                Some((a, b))
+            }
+        }
+    }
+
+    /// How tightly the expression binds when written down.
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Add(_, _) | Self::Subtract(_, _) => 1,
+            Self::Multiply(_, _) | Self::Divide(_, _) => 2,
+            Self::Atom(_) | Self::Unknown => 3,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    /// Writes the expression in infix notation with as few brackets
+    /// as needed, calling the unknown x.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (a, op, b) = match self {
+            Self::Atom(n) if n.integer().is_some_and(|n| n >= 0) => return write!(f, "{n}"),
+            Self::Atom(n) => return write!(f, "({n})"),
+            Self::Unknown => return write!(f, "x"),
+            Self::Add(a, b) => (a, '+', b),
+            Self::Subtract(a, b) => (a, '-', b),
+            Self::Multiply(a, b) => (a, '*', b),
+            Self::Divide(a, b) => (a, '/', b),
+        };
+        let precedence = self.precedence();
+        if a.precedence() < precedence {
+            write!(f, "({a})")?;
+        } else {
+            write!(f, "{a}")?;
+        }
+        write!(f, " {op} ")?;
+        // Subtraction and division are left-associative.
+        if b.precedence() < precedence || b.precedence() == precedence && "-/".contains(op) {
+            write!(f, "({b})")
+        } else {
+            write!(f, "{b}")
+        }
+    }
+}
+
+/// Returns the value of the unknown that makes both sides equal.
+pub fn solve(lhs: &Expr, rhs: &Expr) -> Result<Rational> {
+    let (lhs, rhs) = (lhs.linear()?, rhs.linear()?);
+    let slope = lhs.slope.checked_sub(rhs.slope)?;
+    let intercept = rhs.intercept.checked_sub(lhs.intercept)?;
+    if slope == Rational::from(0) {
+        return Err(Error::new(if intercept == Rational::from(0) {
+            "any number is a solution"
+        } else {
+            "there is no solution"
+        }));
+    }
+    intercept.checked_div(slope)
+}
+
+/// A linear function of the unknown, `slope * x + intercept`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Linear {
+    pub slope: Rational,
+    pub intercept: Rational,
+}
+
+impl Linear {
+    fn constant(value: Rational) -> Self {
+        Self {
+            slope: Rational::from(0),
+            intercept: value,
+        }
+    }
+
+    /// Returns the value of the function if it does not depend on the
+    /// unknown.
+    fn value(&self) -> Option<Rational> {
+        (self.slope == Rational::from(0)).then_some(self.intercept)
+    }
+}
+
+/// An exact fraction, always kept in lowest terms with a positive
+/// denominator. Arithmetic fails rather than overflowing.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Rational {
+    fn new(numerator: Option<i128>, denominator: Option<i128>) -> Result<Self> {
+        let overflow = || Error::new("equation overflows");
+        let (numerator, denominator) = (
+            numerator.ok_or_else(overflow)?,
+            denominator.ok_or_else(overflow)?,
+        );
+        if denominator == 0 {
+            return Err(Error::new("division by zero"));
+        }
+        // Keeping clear of i128::MIN means the signs can always be
+        // flipped.
+        if numerator == i128::MIN || denominator == i128::MIN {
+            return Err(overflow());
+        }
+        let gcd = |mut a: i128, mut b: i128| {
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+            a.abs()
+        };
+        let divisor = gcd(numerator, denominator) * denominator.signum();
+        Ok(Self {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        })
+    }
+
+    /// Returns the number if it is an integer.
+    pub fn integer(&self) -> Option<i128> {
+        (self.denominator == 1).then_some(self.numerator)
+    }
+
+    pub fn checked_add(self, other: Self) -> Result<Self> {
+        let a = self.numerator.checked_mul(other.denominator);
+        let b = other.numerator.checked_mul(self.denominator);
+        Self::new(
+            a.zip(b).and_then(|(a, b)| a.checked_add(b)),
+            self.denominator.checked_mul(other.denominator),
+        )
+    }
+
+    pub fn checked_sub(self, other: Self) -> Result<Self> {
+        let numerator = other
+            .numerator
+            .checked_neg()
+            .ok_or(Error::new("equation overflows"))?;
+        self.checked_add(Self { numerator, ..other })
+    }
+
+    pub fn checked_mul(self, other: Self) -> Result<Self> {
+        Self::new(
+            self.numerator.checked_mul(other.numerator),
+            self.denominator.checked_mul(other.denominator),
+        )
+    }
+
+    /// Divides, failing when dividing by zero.
+    pub fn checked_div(self, other: Self) -> Result<Self> {
+        Self::new(
+            self.numerator.checked_mul(other.denominator),
+            self.denominator.checked_mul(other.numerator),
+        )
+    }
+}
+
+impl From<i128> for Rational {
+    fn from(n: i128) -> Self {
+        Self {
+            numerator: n,
+            denominator: 1,
+        }
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.integer() {
+            Some(n) => write!(f, "{n}"),
+            None => write!(f, "{}/{}", self.numerator, self.denominator),
+        }
+    }
+}
+
 fn parse_monkey(i: &str) -> IResult<&str, Monkey> {
     let num = alt((
         map(u64, Number::Atom),
@@ -190,11 +717,234 @@ hmdt: 32";
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 152);
+        assert_eq!(Day21::part1(&Day21::parse(INPUT).unwrap()), Ok(152));
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(INPUT), 301);
+        assert_eq!(Day21::part2(&Day21::parse(INPUT).unwrap()), Ok(301));
+    }
+
+    #[test]
+    fn equations() {
+        let monkeys = Day21::parse(INPUT).unwrap();
+        let equation = Equation::new("humn", Target::Equal("pppw", "sjmn"), &monkeys).unwrap();
+        assert_eq!(equation.to_string(), "(4 + 2 * (x - 3)) / 4 = 150");
+        assert_eq!(equation.solve(), Ok(Rational::from(301)));
+
+        // Work back from the answer to part 1 to the numbers yelled.
+        let value = Target::Value("root", Rational::from(152));
+        let equation = Equation::new("humn", value, &monkeys).unwrap();
+        assert_eq!(equation.to_string(), "(4 + 2 * (x - 3)) / 4 + 150 = 152");
+        assert_eq!(equation.solve(), Ok(Rational::from(5)));
+        let equation = Equation::new("ljgn", value, &monkeys).unwrap();
+        assert_eq!(equation.to_string(), "(4 + x * 2) / 4 + 150 = 152");
+        assert_eq!(equation.solve(), Ok(Rational::from(2)));
+        let equation = Equation::new("dbpl", value, &monkeys).unwrap();
+        assert_eq!(equation.to_string(), "2 + 30 * x = 152");
+        assert_eq!(equation.solve(), Ok(Rational::from(5)));
+
+        assert_eq!(
+            Equation::new("hmdt", Target::Equal("sllz", "zczc"), &monkeys)
+                .unwrap()
+                .solve(),
+            Err(Error::new("there is no solution"))
+        );
+        assert_eq!(
+            Equation::new("nope", value, &monkeys),
+            Err(Error::new("unknown monkey nope"))
+        );
+    }
+
+    #[test]
+    fn infix() {
+        let atom = |n| Box::new(Expr::Atom(Rational::from(n)));
+        let x = || Box::new(Expr::Unknown);
+        let expr = Expr::Subtract(atom(1), Box::new(Expr::Add(x(), atom(2))));
+        assert_eq!(expr.to_string(), "1 - (x + 2)");
+        let expr = Expr::Subtract(Box::new(Expr::Add(x(), atom(2))), atom(1));
+        assert_eq!(expr.to_string(), "x + 2 - 1");
+        let expr = Expr::Divide(atom(1), Box::new(Expr::Multiply(x(), atom(-2))));
+        assert_eq!(expr.to_string(), "1 / (x * (-2))");
+        let expr = Expr::Add(Box::new(Expr::Divide(x(), atom(3))), atom(2)).reduce();
+        assert_eq!(expr.unwrap().to_string(), "x / 3 + 2");
+        let expr = Expr::Multiply(Box::new(Expr::Divide(atom(1), atom(3))), x()).reduce();
+        assert_eq!(expr.unwrap().to_string(), "(1/3) * x");
+    }
+
+    #[test]
+    fn rationals() {
+        let r = |n, d| Rational::new(Some(n), Some(d)).unwrap();
+        assert_eq!(r(2, 4), r(-1, -2));
+        assert_eq!(r(1, 2).checked_add(r(1, 3)), Ok(r(5, 6)));
+        assert_eq!(r(1, 2).checked_sub(r(3, 4)), Ok(r(-1, 4)));
+        assert_eq!(r(2, 3).checked_mul(r(3, 4)), Ok(r(1, 2)));
+        assert_eq!(r(1, 2).checked_div(r(-1, 4)), Ok(Rational::from(-2)));
+        assert_eq!(
+            r(1, 2).checked_div(r(0, 4)),
+            Err(Error::new("division by zero"))
+        );
+        let large = Rational::from(i128::MAX / 2 + 1);
+        assert_eq!(
+            large.checked_add(large),
+            Err(Error::new("equation overflows"))
+        );
+        assert_eq!(
+            r(1, 3).checked_sub(r(1, i128::MAX)).map(|_| ()),
+            Err(Error::new("equation overflows"))
+        );
+        assert_eq!(r(-3, 6).to_string(), "-1/2");
+        assert_eq!(r(6, 3).to_string(), "2");
+    }
+
+    #[test]
+    fn riddles() {
+        let part2 =
+            |input: &str| Day21::part2(&Day21::parse(&format!("{input}\nhumn: 0")).unwrap());
+        // (humn - 10) / 4 = 3, through a division that is only exact
+        // for the answer.
+        assert_eq!(
+            part2("root: a + b\na: y / x\nx: 4\ny: humn - z\nz: 10\nb: 3"),
+            Ok(22)
+        );
+        assert_eq!(
+            part2("root: a + b\na: x - humn\nx: 1\nb: 3"),
+            Err(Error::new("answer -2 is negative"))
+        );
+        assert_eq!(
+            part2("root: a + b\na: humn * x\nx: 2\nb: 3"),
+            Err(Error::new("answer 3/2 is not an integer"))
+        );
+        assert_eq!(
+            part2("root: a + b\na: x / humn\nx: 12\nb: 4"),
+            Err(Error::new("the unknown appears nonlinearly"))
+        );
+        assert_eq!(
+            part2("root: a + b\na: humn * x\nx: 0\nb: 4"),
+            Err(Error::new("there is no solution"))
+        );
+        assert_eq!(
+            part2("root: a + b\na: humn / x\nx: 0\nb: 4"),
+            Err(Error::new("division by zero"))
+        );
+        assert_eq!(
+            part2(
+                "root: a + humn\na: b * c\nb: d * e\nc: 1000000000000000000\n\
+                 d: 1000000000000000000\ne: 1000000000000000000"
+            ),
+            Err(Error::new("equation overflows"))
+        );
+
+        // Only possible with the unknown in several places.
+        let x = || Box::new(Expr::Unknown);
+        let four = Expr::Atom(Rational::from(4));
+        assert_eq!(
+            solve(&Expr::Multiply(x(), x()), &four),
+            Err(Error::new("the unknown appears nonlinearly"))
+        );
+        assert_eq!(
+            solve(&Expr::Subtract(x(), x()), &four),
+            Err(Error::new("there is no solution"))
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let monkeys = Day21::parse(
+            "root: a * f\na: b * e\nb: c - d\nc: 1\nd: 2147483649\n\
+             e: 4294967296\nf: 4294967296",
+        )
+        .unwrap();
+        let root = monkey("root", &monkeys).unwrap();
+        assert_eq!(
+            root.resolve::<i64>(&monkeys),
+            Err(Error::new("root overflows"))
+        );
+        assert_eq!(
+            monkey("a", &monkeys).unwrap().resolve::<i64>(&monkeys),
+            Ok(i64::MIN)
+        );
+        assert_eq!(root.resolve::<i128>(&monkeys), Ok(-(1 << 95)));
+        assert_eq!(
+            root.resolve::<BigInt>(&monkeys).map(|n| n.to_string()),
+            Ok((-(1i128 << 95)).to_string())
+        );
+
+        let monkeys = Day21::parse("root: a / b\na: 1\nb: c - d\nc: 2\nd: 2").unwrap();
+        assert_eq!(
+            Day21::part1(&monkeys),
+            Err(Error::new("root divides by zero"))
+        );
+        let monkeys = Day21::parse("root: a + b\na: 18446744073709551615\nb: 1").unwrap();
+        assert_eq!(Day21::part1(&monkeys), Err(Error::new("a overflows")));
+    }
+
+    #[test]
+    fn big_integers() {
+        let big = |n: i128| {
+            let magnitude = BigInt::from_u64(n.unsigned_abs() as u64).unwrap();
+            let magnitude = magnitude
+                .checked_add(
+                    &BigInt::from_u64((n.unsigned_abs() >> 64) as u64)
+                        .unwrap()
+                        .checked_mul(&BigInt::new(false, vec![0, 0, 1]))
+                        .unwrap(),
+                )
+                .unwrap();
+            if n < 0 {
+                magnitude.negate()
+            } else {
+                magnitude
+            }
+        };
+        let cases = [
+            0,
+            1,
+            -1,
+            7,
+            -7,
+            2,
+            1 << 40,
+            -(1 << 70) + 12345,
+            i64::MAX as i128 * 3,
+        ];
+        for a in cases {
+            assert_eq!(big(a).to_string(), a.to_string());
+            for b in cases {
+                assert_eq!(big(a).checked_add(&big(b)), Some(big(a + b)));
+                assert_eq!(big(a).checked_sub(&big(b)), Some(big(a - b)));
+                if let Some(product) = a.checked_mul(b) {
+                    assert_eq!(big(a).checked_mul(&big(b)), Some(big(product)));
+                }
+                assert_eq!(big(a).checked_div(&big(b)), a.checked_div(b).map(big));
+            }
+        }
+        let quintillion = big(10i128.pow(18));
+        let power = (0..3).fold(big(1), |n, _| n.checked_mul(&quintillion).unwrap());
+        assert_eq!(power.to_string(), format!("1{}", "0".repeat(54)));
+        assert_eq!(
+            power.checked_div(&quintillion.checked_mul(&big(-7)).unwrap()),
+            Some(big(-142857142857142857142857142857142857))
+        );
+    }
+
+    #[test]
+    fn trees() {
+        assert_eq!(
+            Day21::parse("root: a + a\na: 1").map(|_| ()),
+            Err(Error::new("a is used by both root and root"))
+        );
+        assert_eq!(
+            Day21::parse("root: a + b\na: b * c\nb: 1\nc: 2").map(|_| ()),
+            Err(Error::new("b is used by both root and a"))
+        );
+        assert_eq!(
+            Day21::parse("root: a + b\na: 1\nb: c - d\nc: d * b\nd: 2").map(|_| ()),
+            Err(Error::new("d is used by both b and c"))
+        );
+        assert_eq!(
+            Day21::parse("root: a + b\na: 1\nb: 2\nc: d * e\nd: c - f\ne: 1\nf: 1").map(|_| ()),
+            Err(Error::new("c depends on itself"))
+        );
     }
 }
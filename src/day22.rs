@@ -1,93 +1,109 @@
 use nom::{
     branch::alt,
     character::complete::{one_of, u8},
-    combinator::{all_consuming, map},
+    combinator::map,
     multi::many1,
     IResult,
 };
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/22.txt");
-    format!("{}\n{}", part1(input), part2(input))
-}
+use crate::error::nom;
+use crate::{Error, Result, Solution};
 
-fn part1(input: &str) -> usize {
-    let (map, instructions) = input.trim_end().split_once("\n\n").expect("invalid input");
+pub const INPUT: &str = include_str!("../inputs/22.txt");
 
-    let instructions = all_consuming(many1(parse_instruction))(instructions)
-        .expect("failed to parse instructions")
-        .1;
+pub struct Day22;
 
-    let mut map = Map::new(map, false);
-    for instruction in instructions {
-        map.execute(instruction);
-    }
-    1000 * (map.position / map.width + 1) + 4 * (map.position % map.width + 1) + map.facing.score()
-}
+impl Solution for Day22 {
+    /// The map, and the path to follow on it.
+    type Input = (Map, Vec<Instruction>);
+    type Part1 = usize;
+    type Part2 = usize;
+
+    const DAY: u8 = 22;
 
-fn part2(input: &str) -> String {
-    let (map, instructions) = input.trim_end().split_once("\n\n").expect("invalid input");
+    fn parse(input: &str) -> Result<Self::Input> {
+        let (map, instructions) = input
+            .trim_end()
+            .split_once("\n\n")
+            .ok_or(Error::new("failed to split map and path"))?;
 
-    let instructions = all_consuming(many1(parse_instruction))(instructions)
-        .expect("failed to parse instructions")
-        .1;
+        let instructions = nom(many1(parse_instruction), "path")(instructions)
+            .map_err(|e| e.within(input, instructions))?;
 
-    let mut map = Map::new(map, true);
-    for instruction in instructions {
-        map.execute(instruction);
+        Ok((Map::new(map)?, instructions))
+    }
+
+    fn part1((map, instructions): &Self::Input) -> Result<usize> {
+        let mut map = map.clone();
+        for instruction in instructions {
+            map.execute(*instruction);
+        }
+        Ok(map.password())
+    }
+
+    fn part2((map, instructions): &Self::Input) -> Result<usize> {
+        let mut map = map.clone();
+        map.cube = Some(Cube::fold(&map)?);
+        for instruction in instructions {
+            map.execute(*instruction);
+        }
+        Ok(map.password())
     }
-    format!(
-        "{}",
-        1000 * (map.position / map.width + 1)
-            + 4 * (map.position % map.width + 1)
-            + map.facing.score()
-    )
 }
 
-struct Map {
+#[derive(Clone, Debug)]
+pub struct Map {
     inner: Vec<Cell>,
     width: usize,
     position: usize,
     facing: Facing,
-    cubic: bool,
+    /// How to wrap around the edges of the map, if it is folded into
+    /// a cube.
+    cube: Option<Cube>,
 }
 
 impl Map {
-    fn new(i: &str, cubic: bool) -> Self {
-        let width = i.lines().map(str::len).max().unwrap();
-        let map_rows: Vec<&str> = i.lines().collect();
-        let inner: Vec<Cell> = map_rows
+    pub fn new(i: &str) -> Result<Self> {
+        let width = i.lines().map(str::len).max().unwrap_or_default();
+        let mut inner = vec![];
+        for row in i.lines() {
+            for (x, c) in row.char_indices() {
+                inner.push(match c {
+                    ' ' => Cell::Void,
+                    '.' => Cell::Empty,
+                    '#' => Cell::Wall,
+                    _ => return Err(Error::new("invalid cell").located(i, &row[x..])),
+                });
+            }
+            // Pad the back of the row if it's too short.
+            inner.extend((row.len()..width).map(|_| Cell::Void));
+        }
+        let position = inner
             .iter()
-            .flat_map(|r| {
-                let mut cells: Vec<Cell> = r
-                    .chars()
-                    .map(|c| match c {
-                        ' ' => Cell::Void,
-                        '.' => Cell::Empty,
-                        '#' => Cell::Wall,
-                        _ => panic!("invalid cell"),
-                    })
-                    .collect();
-                // Pad the back of the row if it's too short.
-                cells.extend((0..width - cells.len()).map(|_| Cell::Void));
-                cells
-            })
-            .collect();
-        let position = inner.iter().position(|c| *c == Cell::Empty).unwrap();
+            .position(|c| *c == Cell::Empty)
+            .ok_or(Error::new("no open tile to start on"))?;
         let facing = Facing::Right;
-        Self {
+        Ok(Self {
             inner,
             width,
             position,
             facing,
-            cubic,
-        }
+            cube: None,
+        })
+    }
+
+    /// Returns the final password, based on the current position and
+    /// facing.
+    pub fn password(&self) -> usize {
+        1000 * (self.position / self.width + 1)
+            + 4 * (self.position % self.width + 1)
+            + self.facing.score()
     }
 
     /// Executes an instruction, either turning, or walking a given
     /// distance, stopping if we hit a wall, and wrapping if we walk
     /// off the map or into the void.
-    fn execute(&mut self, instruction: Instruction) {
+    pub fn execute(&mut self, instruction: Instruction) {
         match instruction {
             Instruction::Walk(dist) => {
                 for _ in 0..dist {
@@ -104,253 +120,13 @@ impl Map {
         }
     }
 
-    /// Returns a 3D position & facing for part 2. X, Y are 0 in the
-    /// top-left corner of the front face, Z extends "into" the cube.
-    /// The mapping here is hard-coded to my layout. Zero coordinates
-    /// are considered on the outside of the cube, as are face_size +
-    /// 1.
-    fn position_3d(&self) -> ((usize, usize, usize), Facing3D) {
-        let face_size = self.face_size();
-        // Coordinates on face.
-        let x = (self.position % self.width) % face_size;
-        let y = (self.position / self.width) % face_size;
-        let (face_x, face_y) = self.cube_face(self.position);
-        match (face_x, face_y) {
-            // Face A, front
-            (1, 0) => ((x + 1, y + 1, 0), Facing3D::from(self.facing)),
-            // Face B, bottom
-            (1, 1) => (
-                (x + 1, face_size + 1, y + 1),
-                Facing3D::from(self.facing).rotate(Axis::X, 1),
-            ),
-            // Face C, back (upside down)
-            (1, 2) => (
-                (x + 1, face_size - y, face_size + 1),
-                Facing3D::from(self.facing).rotate(Axis::X, 2),
-            ),
-            // Face D, top (turned)
-            (0, 3) => (
-                (y + 1, 0, x + 1),
-                Facing3D::from(self.facing)
-                    .rotate(Axis::X, 3)
-                    .rotate(Axis::Y, 3),
-            ),
-            // Face E, left (upside down)
-            (0, 2) => (
-                (0, face_size - y, x + 1),
-                Facing3D::from(self.facing)
-                    .rotate(Axis::Y, 1)
-                    .rotate(Axis::X, 2),
-            ),
-            // Face F, right
-            (2, 0) => (
-                (face_size + 1, y + 1, x + 1),
-                Facing3D::from(self.facing).rotate(Axis::Y, 3),
-            ),
-            _ => unreachable!("unexpected cube face"),
-        }
-    }
-
-    /// Given a 3D position & facing, returns the corresponding 2D
-    /// coordinates and facing. Hard-coded to my layout.
-    fn position_2d(&self, coords: (usize, usize, usize), facing: Facing3D) -> (usize, Facing) {
-        let face_size = self.face_size();
-        let (x, y, z) = coords;
-        let (face_x, face_y) = match coords {
-            // Face E, left
-            (0, _, _) => (0, 2),
-            // Face F, right
-            (x, _, _) if x == face_size + 1 => (2, 0),
-            // Face D, top
-            (_, 0, _) => (0, 3),
-            // Face B, bottom
-            (_, y, _) if y == face_size + 1 => (1, 1),
-            // Face A, front
-            (_, _, 0) => (1, 0),
-            // Face C, back
-            (_, _, z) if z == face_size + 1 => (1, 2),
-            _ => unreachable!("unexpeced 3d coordinates"),
-        };
-        let to_idx = |x_source, y_source| {
-            // Face offset from top.
-            face_y * face_size * self.width
-            // Face offset from left.
-                + face_x * face_size
-                // Offset from face top.
-                + y_source * self.width
-                // Offset from face left.
-                + x_source
-        };
-        match (face_x, face_y) {
-            // Face A, front
-            (1, 0) => (to_idx(x - 1, y - 1), facing.into()),
-            // Face B, bottom
-            (1, 1) => (to_idx(x - 1, z - 1), facing.rotate(Axis::X, 3).into()),
-            // Face C, back (upside down)
-            (1, 2) => (
-                to_idx(x - 1, face_size - y),
-                facing.rotate(Axis::X, 2).into(),
-            ),
-            // Face D, top (turned)
-            (0, 3) => (
-                to_idx(z - 1, x - 1),
-                facing.rotate(Axis::Y, 1).rotate(Axis::X, 1).into(),
-            ),
-            // Face E, left (upside down)
-            (0, 2) => (
-                to_idx(z - 1, face_size - y),
-                facing.rotate(Axis::X, 2).rotate(Axis::Y, 3).into(),
-            ),
-            // Face F, right
-            (2, 0) => (to_idx(z - 1, y - 1), facing.rotate(Axis::Y, 1).into()),
-            _ => unreachable!("unexpected face coordinates"),
-        }
-    }
-
     /// Returns the index of the next step in a given facing. Returns None
     /// if blocked.
     fn next_cell(&mut self) -> Option<usize> {
-        if self.cubic {
-            let face_size = self.face_size();
-            let ((mut x, mut y, mut z), mut f3d) = self.position_3d();
-            match f3d {
-                Facing3D::Up => {
-                    if y == 1 && z == 0 {
-                        // Wrapping on face A.
-                        f3d = Facing3D::In;
-                        z += 1;
-                    } else if y == 1 && z == face_size + 1 {
-                        // Wrapping on face C.
-                        f3d = Facing3D::Out;
-                        z -= 1;
-                    } else if y == 1 && x == 0 {
-                        // Wrapping on face E.
-                        f3d = Facing3D::Right;
-                        x += 1;
-                    } else if y == 1 && x == face_size + 1 {
-                        // Wrapping on face F.
-                        f3d = Facing3D::Left;
-                        x -= 1;
-                    } else if y == 1 {
-                        panic!("unexpected wrapping position")
-                    }
-                    y -= 1;
-                }
-                Facing3D::Down => {
-                    if y == face_size && z == 0 {
-                        // Wrapping on face A.
-                        f3d = Facing3D::In;
-                        z += 1;
-                    } else if y == face_size && z == face_size + 1 {
-                        // Wrapping on face C.
-                        f3d = Facing3D::Out;
-                        z -= 1;
-                    } else if y == face_size && x == 0 {
-                        // Wrapping on face E.
-                        f3d = Facing3D::Right;
-                        x += 1;
-                    } else if y == face_size && x == face_size + 1 {
-                        // Wrapping on face F.
-                        f3d = Facing3D::Left;
-                        x -= 1;
-                    } else if y == face_size {
-                        panic!("unexpected wrapping position")
-                    }
-                    y += 1;
-                }
-                Facing3D::Left => {
-                    if x == 1 && z == 0 {
-                        // Wrapping on face A.
-                        f3d = Facing3D::In;
-                        z += 1;
-                    } else if x == 1 && z == face_size + 1 {
-                        // Wrapping on face C.
-                        f3d = Facing3D::Out;
-                        z -= 1;
-                    } else if x == 1 && y == 0 {
-                        // Wrapping on face D.
-                        f3d = Facing3D::Down;
-                        y += 1;
-                    } else if x == 1 && y == face_size + 1 {
-                        // Wrapping on face B.
-                        f3d = Facing3D::Up;
-                        y -= 1;
-                    } else if x == 1 {
-                        panic!("unexpected wrapping position: {x},{y},{z}");
-                    }
-                    x -= 1;
-                }
-                Facing3D::Right => {
-                    if x == face_size && z == 0 {
-                        // Wrapping on face A.
-                        f3d = Facing3D::In;
-                        z += 1;
-                    } else if x == face_size && z == face_size + 1 {
-                        // Wrapping on face C.
-                        f3d = Facing3D::Out;
-                        z -= 1;
-                    } else if x == face_size && y == 0 {
-                        // Wrapping on face D.
-                        f3d = Facing3D::Down;
-                        y += 1;
-                    } else if x == face_size && y == face_size + 1 {
-                        // Wrapping on face B.
-                        f3d = Facing3D::Up;
-                        y -= 1;
-                    } else if x == face_size {
-                        panic!("unexpected wrapping position: {x},{y},{z}");
-                    }
-                    x += 1;
-                }
-                Facing3D::In => {
-                    if z == face_size && y == 0 {
-                        // Wrapping on face D.
-                        f3d = Facing3D::Down;
-                        y += 1;
-                    } else if z == face_size && y == face_size + 1 {
-                        // Wrapping on face B.
-                        f3d = Facing3D::Up;
-                        y -= 1;
-                    } else if z == face_size && x == 0 {
-                        // Wrapping on face E.
-                        f3d = Facing3D::Right;
-                        x += 1;
-                    } else if z == face_size && x == face_size + 1 {
-                        // Wrapping on face F.
-                        f3d = Facing3D::Left;
-                        x -= 1;
-                    } else if z == face_size {
-                        panic!("unexpected wrapping position: {x},{y},{z}");
-                    }
-                    z += 1;
-                }
-                Facing3D::Out => {
-                    if z == 1 && y == 0 {
-                        // Wrapping on face D.
-                        f3d = Facing3D::Down;
-                        y += 1;
-                    } else if z == 1 && y == face_size + 1 {
-                        // Wrapping on face B.
-                        f3d = Facing3D::Up;
-                        y -= 1;
-                    } else if z == 1 && x == 0 {
-                        // Wrapping on face E.
-                        f3d = Facing3D::Right;
-                        x += 1;
-                    } else if z == 1 && x == face_size + 1 {
-                        // Wrapping on face F.
-                        f3d = Facing3D::Left;
-                        x -= 1;
-                    } else if z == 1 {
-                        panic!("unexpected wrapping position: {x},{y},{z}");
-                    }
-                    z -= 1;
-                }
-            };
-            let (next, f2d) = self.position_2d((x, y, z), f3d);
-
+        if let Some(cube) = &self.cube {
+            let (next, facing) = cube.step(self.position, self.facing);
             if self.inner[next] == Cell::Empty {
-                self.facing = f2d;
+                self.facing = facing;
                 Some(next)
             } else {
                 None
@@ -388,19 +164,6 @@ impl Map {
             }
         }
     }
-
-    /// Returns the size of each cube face.
-    fn face_size(&self) -> usize {
-        ((self.inner.iter().filter(|&c| *c != Cell::Void).count() / 6) as f32).sqrt() as usize
-    }
-
-    /// Returns the cube face a given coordinate is in, as (x, y).
-    fn cube_face(&self, idx: usize) -> (usize, usize) {
-        let face_size = self.face_size();
-        let row = idx / self.width;
-        let column = idx % self.width;
-        (column / face_size, row / face_size)
-    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -411,13 +174,13 @@ enum Cell {
 }
 
 #[derive(Copy, Clone, Debug)]
-enum Instruction {
+pub enum Instruction {
     Walk(u8),
     TurnLeft,
     TurnRight,
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 enum Facing {
     Up,
     Down,
@@ -451,78 +214,198 @@ impl Facing {
     }
 }
 
-impl From<Facing3D> for Facing {
-    /// Creates a 2D facing from a 3D one. The 3D facing must not be
-    /// In or Out.
-    fn from(f: Facing3D) -> Self {
-        match f {
-            Facing3D::Up => Self::Up,
-            Facing3D::Down => Self::Down,
-            Facing3D::Left => Self::Left,
-            Facing3D::Right => Self::Right,
-            _ => unreachable!("unexpected 3d facing"),
-        }
-    }
+/// A direction in 3D, as a unit vector.
+type Vector = [i8; 3];
+
+fn reverse(v: Vector) -> Vector {
+    v.map(|c| -c)
 }
 
+/// Where a face of the net ends up once the map is folded.
 #[derive(Copy, Clone, Debug)]
-enum Facing3D {
-    Up,
-    Down,
-    Left,
-    Right,
-    In,
-    Out,
+struct Face {
+    /// The map coordinates of the face's top-left tile.
+    x: usize,
+    y: usize,
+    /// The direction the face points, out of the cube.
+    normal: Vector,
+    /// The directions of walking right and down across the face.
+    right: Vector,
+    down: Vector,
 }
 
-#[allow(dead_code)]
-#[derive(Copy, Clone, Debug)]
-enum Axis {
-    X,
-    Y,
-    Z,
+impl Face {
+    /// Returns the direction of walking across the face with a given
+    /// facing.
+    fn direction(&self, facing: Facing) -> Vector {
+        match facing {
+            Facing::Right => self.right,
+            Facing::Down => self.down,
+            Facing::Left => reverse(self.right),
+            Facing::Up => reverse(self.down),
+        }
+    }
 }
 
-impl Facing3D {
-    /// Rotate this facing turns times clockwise along the axis.
-    fn rotate(&self, axis: Axis, turns: usize) -> Self {
-        let one_turn = |f| match (f, axis) {
-            (Self::Left, Axis::X) => Self::Left,
-            (Self::Right, Axis::X) => Self::Right,
-            (Self::Up, Axis::X) => Self::Out,
-            (Self::Out, Axis::X) => Self::Down,
-            (Self::Down, Axis::X) => Self::In,
-            (Self::In, Axis::X) => Self::Up,
-            (Self::Up, Axis::Y) => Self::Up,
-            (Self::Down, Axis::Y) => Self::Down,
-            (Self::Right, Axis::Y) => Self::Out,
-            (Self::Out, Axis::Y) => Self::Left,
-            (Self::Left, Axis::Y) => Self::In,
-            (Self::In, Axis::Y) => Self::Right,
-            (Self::In, Axis::Z) => Self::In,
-            (Self::Out, Axis::Z) => Self::Out,
-            (Self::Up, Axis::Z) => Self::Right,
-            (Self::Right, Axis::Z) => Self::Down,
-            (Self::Down, Axis::Z) => Self::Left,
-            (Self::Left, Axis::Z) => Self::Up,
+/// The map folded into a cube, for wrapping around its edges in
+/// part 2.
+#[derive(Clone, Debug)]
+struct Cube {
+    /// The width of each face.
+    size: usize,
+    /// The width of the map.
+    width: usize,
+    faces: Vec<Face>,
+}
+
+impl Cube {
+    /// Folds the map into a cube. The face size is worked out from
+    /// the number of tiles, and the net may be any of the 11 that
+    /// fold into a cube.
+    fn fold(map: &Map) -> Result<Self> {
+        let error = || Error::new("map does not fold into a cube");
+        let tiles = map.inner.iter().filter(|&c| *c != Cell::Void).count();
+        let size = (1..)
+            .take_while(|n| 6 * n * n <= tiles)
+            .last()
+            .ok_or_else(error)?;
+        if 6 * size * size != tiles {
+            return Err(error());
+        }
+
+        // The net is laid out on a grid of faces, so the top-left
+        // tiles of the faces are at multiples of the face size.
+        let height = map.inner.len() / map.width;
+        let is_face = |x: usize, y: usize| {
+            x + size <= map.width
+                && y + size <= height
+                && (y..y + size)
+                    .all(|y| (x..x + size).all(|x| map.inner[y * map.width + x] != Cell::Void))
         };
-        let mut rv = *self;
-        for _ in 0..turns {
-            rv = one_turn(rv);
+        let corners: Vec<_> = (0..height)
+            .step_by(size)
+            .flat_map(|y| (0..map.width).step_by(size).map(move |x| (x, y)))
+            .filter(|&(x, y)| is_face(x, y))
+            .collect();
+        if corners.len() != 6 {
+            return Err(error());
         }
-        rv
+
+        // Spread out from the first face, folding each neighbour in the
+        // net down over the edge they share.
+        let (x, y) = corners[0];
+        let mut faces = vec![Face {
+            x,
+            y,
+            normal: [0, 0, -1],
+            right: [1, 0, 0],
+            down: [0, 1, 0],
+        }];
+        let mut i = 0;
+        while let Some(&face) = faces.get(i) {
+            i += 1;
+            for facing in [Facing::Right, Facing::Down, Facing::Left, Facing::Up] {
+                let (x, y) = match facing {
+                    Facing::Right => (face.x + size, face.y),
+                    Facing::Down => (face.x, face.y + size),
+                    Facing::Left => (face.x.wrapping_sub(size), face.y),
+                    Facing::Up => (face.x, face.y.wrapping_sub(size)),
+                };
+                if !corners.contains(&(x, y)) || faces.iter().any(|f| (f.x, f.y) == (x, y)) {
+                    continue;
+                }
+                // Walking over the fold turns us to head back along the
+                // old face's normal.
+                let (right, down) = match facing {
+                    Facing::Right => (reverse(face.normal), face.down),
+                    Facing::Down => (face.right, reverse(face.normal)),
+                    Facing::Left => (face.normal, face.down),
+                    Facing::Up => (face.right, face.normal),
+                };
+                let normal = face.direction(facing);
+                if faces.iter().any(|f| f.normal == normal) {
+                    return Err(Error::new("map overlaps itself when folded into a cube"));
+                }
+                faces.push(Face {
+                    x,
+                    y,
+                    normal,
+                    right,
+                    down,
+                });
+            }
+        }
+        if faces.len() != 6 {
+            return Err(error());
+        }
+
+        Ok(Self {
+            size,
+            width: map.width,
+            faces,
+        })
+    }
+
+    fn face(&self, position: usize) -> &Face {
+        let (x, y) = (position % self.width, position / self.width);
+        self.faces
+            .iter()
+            .find(|f| (f.x..f.x + self.size).contains(&x) && (f.y..f.y + self.size).contains(&y))
+            .expect("position is on the cube")
     }
-}
 
-impl From<Facing> for Facing3D {
-    /// Returns the same facing but as 3D.
-    fn from(f: Facing) -> Self {
-        match f {
-            Facing::Up => Self::Up,
-            Facing::Down => Self::Down,
-            Facing::Left => Self::Left,
-            Facing::Right => Self::Right,
+    /// Returns the index and facing after a step from a position,
+    /// wrapping around to the next face if we walk off an edge.
+    fn step(&self, position: usize, facing: Facing) -> (usize, Facing) {
+        let face = self.face(position);
+        let (dx, dy) = (
+            position % self.width - face.x,
+            position / self.width - face.y,
+        );
+        let last = self.size - 1;
+        match facing {
+            Facing::Right if dx < last => return (position + 1, facing),
+            Facing::Down if dy < last => return (position + self.width, facing),
+            Facing::Left if dx > 0 => return (position - 1, facing),
+            Facing::Up if dy > 0 => return (position - self.width, facing),
+            _ => (),
         }
+
+        // The next face is on the side of the cube we walked towards,
+        // and we walk across it away from the face we left.
+        let direction = face.direction(facing);
+        let next = self
+            .faces
+            .iter()
+            .find(|f| f.normal == direction)
+            .expect("cube has a face on every side");
+        let new_facing = [Facing::Right, Facing::Down, Facing::Left, Facing::Up]
+            .into_iter()
+            .find(|&f| next.direction(f) == reverse(face.normal))
+            .expect("faces meet at an edge");
+
+        // Keep our place along the shared edge, which may run the other
+        // way on the next face.
+        let (edge, offset) = match facing {
+            Facing::Left | Facing::Right => (face.down, dy),
+            Facing::Up | Facing::Down => (face.right, dx),
+        };
+        let next_edge = match new_facing {
+            Facing::Left | Facing::Right => next.down,
+            Facing::Up | Facing::Down => next.right,
+        };
+        let offset = if next_edge == edge {
+            offset
+        } else {
+            last - offset
+        };
+        let (dx, dy) = match new_facing {
+            Facing::Right => (0, offset),
+            Facing::Down => (offset, 0),
+            Facing::Left => (last, offset),
+            Facing::Up => (offset, last),
+        };
+        ((next.y + dy) * self.width + next.x + dx, new_facing)
     }
 }
 
@@ -560,6 +443,116 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 6032);
+        assert_eq!(Day22::part1(&Day22::parse(INPUT).unwrap()), Ok(6032));
+    }
+
+    #[test]
+    fn invalid_cell() {
+        let input = INPUT.replacen(".#..", ".#x.", 1);
+        assert_eq!(
+            Day22::parse(&input).unwrap_err().to_string(),
+            "line 2, column 11: invalid cell"
+        );
+    }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(Day22::part2(&Day22::parse(INPUT).unwrap()), Ok(5031));
+    }
+
+    fn turn_around(facing: Facing) -> Facing {
+        facing
+            .turn(Instruction::TurnLeft)
+            .turn(Instruction::TurnLeft)
+    }
+
+    /// Checks that stepping off every edge of the cube and then
+    /// straight back returns to where we started.
+    fn check_edges(map: &Map) {
+        let cube = Cube::fold(map).unwrap();
+        let last = cube.size - 1;
+        for face in &cube.faces {
+            for offset in 0..cube.size {
+                for (facing, dx, dy) in [
+                    (Facing::Right, last, offset),
+                    (Facing::Down, offset, last),
+                    (Facing::Left, 0, offset),
+                    (Facing::Up, offset, 0),
+                ] {
+                    let start = (face.y + dy) * cube.width + face.x + dx;
+                    let (next, new_facing) = cube.step(start, facing);
+                    assert_ne!(cube.face(next).normal, face.normal);
+                    let (end, end_facing) = cube.step(next, turn_around(new_facing));
+                    assert_eq!((end, end_facing), (start, turn_around(facing)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn example_net() {
+        let (map, _) = Day22::parse(INPUT).unwrap();
+        let cube = Cube::fold(&map).unwrap();
+        assert_eq!(cube.size, 4);
+        // The two wraps described in the puzzle.
+        assert_eq!(
+            cube.step(5 * 16 + 11, Facing::Right),
+            (8 * 16 + 14, Facing::Down)
+        );
+        assert_eq!(
+            cube.step(11 * 16 + 10, Facing::Down),
+            (7 * 16 + 1, Facing::Up)
+        );
+        check_edges(&map);
+    }
+
+    #[test]
+    fn real_net() {
+        let (map, instructions) = Day22::parse(super::INPUT).unwrap();
+        assert_eq!(Cube::fold(&map).unwrap().size, 50);
+        check_edges(&map);
+        assert_eq!(Day22::part2(&(map, instructions)), Ok(110400));
+    }
+
+    #[test]
+    fn all_nets() {
+        let nets = [
+            "#...|####|#...",
+            "#...|####|.#..",
+            "#...|####|..#.",
+            "#...|####|...#",
+            ".#..|####|.#..",
+            ".#..|####|..#.",
+            "##..|.###|.#..",
+            "##..|.###|..#.",
+            "##..|.###|...#",
+            "##..|.##.|..##",
+            "###..|..###",
+        ];
+        for net in nets {
+            // Scale each face up, so there is an edge to walk along.
+            let map = net
+                .split('|')
+                .map(|row| {
+                    let row: String = row
+                        .chars()
+                        .flat_map(|c| [if c == '#' { '.' } else { ' ' }; 3])
+                        .collect();
+                    [row.as_str(); 3].join("\n")
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            check_edges(&Map::new(&map).unwrap());
+        }
+        let map = Map::new("... ...").unwrap();
+        assert_eq!(
+            Cube::fold(&map).unwrap_err(),
+            Error::new("map does not fold into a cube")
+        );
+        let map = Map::new(".....\n.").unwrap();
+        assert_eq!(
+            Cube::fold(&map).unwrap_err(),
+            Error::new("map overlaps itself when folded into a cube")
+        );
     }
 }
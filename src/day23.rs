@@ -1,120 +1,132 @@
 use fxhash::{FxHashMap, FxHashSet};
 use itertools::Itertools;
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/23.txt");
-    format!("{}\n{}", part1(input), part2(input))
-}
+use crate::{Error, Result, Solution};
 
-fn part1(input: &str) -> usize {
-    let mut elves = parse_elves(input);
-
-    // Three relative coordinate pairs to check, and a relative
-    // coordinate pair to propose if that check succeeds.
-    let mut preferences = [
-        // North
-        ([(-1, -1), (0, -1), (1, -1)], (0, -1)),
-        // South
-        ([(-1, 1), (0, 1), (1, 1)], (0, 1)),
-        // West
-        ([(-1, -1), (-1, 0), (-1, 1)], (-1, 0)),
-        // East
-        ([(1, -1), (1, 0), (1, 1)], (1, 0)),
-    ];
-
-    for _ in 0..10 {
-        // Tracks how many elves propose to go to a given tile.
-        let mut proposal_counts: FxHashMap<Tile, usize> = FxHashMap::default();
-        // Create a hash set of elf positions for faster checking of
-        // neighbours.
-        let taken: FxHashSet<&Elf> = elves.iter().collect();
-        // Gather proposals.
-        let proposals: Vec<Option<Tile>> = elves
-            .iter()
-            .map(|e| propose_move(e, &preferences, &taken, &mut proposal_counts))
-            .collect();
-        // Execute proposed moves.
-        elves
-            .iter_mut()
-            .zip(proposals)
-            .for_each(|(elf, proposal)| execute_move(elf, proposal, &proposal_counts));
-        // Rotate movement direction preferences.
-        preferences.rotate_left(1);
-    }
+pub const INPUT: &str = include_str!("../inputs/23.txt");
 
-    let (box_width, box_height) = bounding_box(&elves);
-    box_width * box_height - elves.len()
-}
+pub struct Day23;
 
-fn part2(input: &str) -> usize {
-    let mut elves = parse_elves(input);
-
-    // Three relative coordinate pairs to check, and a relative
-    // coordinate pair to propose if that check succeeds.
-    let mut preferences = [
-        // North
-        ([(-1, -1), (0, -1), (1, -1)], (0, -1)),
-        // South
-        ([(-1, 1), (0, 1), (1, 1)], (0, 1)),
-        // West
-        ([(-1, -1), (-1, 0), (-1, 1)], (-1, 0)),
-        // East
-        ([(1, -1), (1, 0), (1, 1)], (1, 0)),
-    ];
-
-    let mut i = 0;
-
-    loop {
-        i += 1;
-        // Tracks how many elves propose to go to a given tile.
-        let mut proposal_counts: FxHashMap<Tile, usize> = FxHashMap::default();
-        // Create a hash set of elf positions for faster checking of
-        // neighbours.
-        let taken: FxHashSet<&Elf> = elves.iter().collect();
-        // Gather proposals.
-        let proposals: Vec<Option<Tile>> = elves
-            .iter()
-            .map(|e| propose_move(e, &preferences, &taken, &mut proposal_counts))
-            .collect();
-        if proposals.iter().all(Option::is_none) {
-            break;
+impl Solution for Day23 {
+    /// The starting positions of all elves.
+    type Input = Vec<Elf>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    const DAY: u8 = 23;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_elves(input)
+    }
+
+    fn part1(elves: &Self::Input) -> Result<usize> {
+        let mut elves = elves.to_vec();
+
+        // Three relative coordinate pairs to check, and a relative
+        // coordinate pair to propose if that check succeeds.
+        let mut preferences = [
+            // North
+            ([(-1, -1), (0, -1), (1, -1)], (0, -1)),
+            // South
+            ([(-1, 1), (0, 1), (1, 1)], (0, 1)),
+            // West
+            ([(-1, -1), (-1, 0), (-1, 1)], (-1, 0)),
+            // East
+            ([(1, -1), (1, 0), (1, 1)], (1, 0)),
+        ];
+
+        for _ in 0..10 {
+            // Tracks how many elves propose to go to a given tile.
+            let mut proposal_counts: FxHashMap<Tile, usize> = FxHashMap::default();
+            // Create a hash set of elf positions for faster checking of
+            // neighbours.
+            let taken: FxHashSet<&Elf> = elves.iter().collect();
+            // Gather proposals.
+            let proposals: Vec<Option<Tile>> = elves
+                .iter()
+                .map(|e| propose_move(e, &preferences, &taken, &mut proposal_counts))
+                .collect();
+            // Execute proposed moves.
+            elves
+                .iter_mut()
+                .zip(proposals)
+                .for_each(|(elf, proposal)| execute_move(elf, proposal, &proposal_counts));
+            // Rotate movement direction preferences.
+            preferences.rotate_left(1);
         }
-        // Execute proposed moves.
-        elves
-            .iter_mut()
-            .zip(proposals)
-            .for_each(|(elf, proposal)| execute_move(elf, proposal, &proposal_counts));
-        // Rotate movement direction preferences.
-        preferences.rotate_left(1);
+
+        let (box_width, box_height) = bounding_box(&elves);
+        Ok(box_width * box_height - elves.len())
     }
 
-    i
+    fn part2(elves: &Self::Input) -> Result<usize> {
+        let mut elves = elves.to_vec();
+
+        // Three relative coordinate pairs to check, and a relative
+        // coordinate pair to propose if that check succeeds.
+        let mut preferences = [
+            // North
+            ([(-1, -1), (0, -1), (1, -1)], (0, -1)),
+            // South
+            ([(-1, 1), (0, 1), (1, 1)], (0, 1)),
+            // West
+            ([(-1, -1), (-1, 0), (-1, 1)], (-1, 0)),
+            // East
+            ([(1, -1), (1, 0), (1, 1)], (1, 0)),
+        ];
+
+        let mut i = 0;
+
+        loop {
+            i += 1;
+            // Tracks how many elves propose to go to a given tile.
+            let mut proposal_counts: FxHashMap<Tile, usize> = FxHashMap::default();
+            // Create a hash set of elf positions for faster checking of
+            // neighbours.
+            let taken: FxHashSet<&Elf> = elves.iter().collect();
+            // Gather proposals.
+            let proposals: Vec<Option<Tile>> = elves
+                .iter()
+                .map(|e| propose_move(e, &preferences, &taken, &mut proposal_counts))
+                .collect();
+            if proposals.iter().all(Option::is_none) {
+                break;
+            }
+            // Execute proposed moves.
+            elves
+                .iter_mut()
+                .zip(proposals)
+                .for_each(|(elf, proposal)| execute_move(elf, proposal, &proposal_counts));
+            // Rotate movement direction preferences.
+            preferences.rotate_left(1);
+        }
+
+        Ok(i)
+    }
 }
 
-fn parse_elves(input: &str) -> Vec<Elf> {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(linum, line)| {
-            line.chars()
-                .enumerate()
-                .filter_map(|(colnum, c)| {
-                    if c == '#' {
-                        Some((colnum as isize, linum as isize))
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>()
-        })
-        .collect()
+fn parse_elves(input: &str) -> Result<Vec<Elf>> {
+    let mut elves = vec![];
+    for (linum, line) in input.lines().enumerate() {
+        for (colnum, (i, c)) in line.char_indices().enumerate() {
+            match c {
+                '#' => elves.push((colnum as isize, linum as isize)),
+                '.' => {}
+                _ => return Err(Error::new("invalid tile").located(input, &line[i..])),
+            }
+        }
+    }
+    if elves.is_empty() {
+        return Err(Error::new("no elves"));
+    }
+    Ok(elves)
 }
 
 /// (x, y) coordinate pair, where x is right, and y is down. The
 /// origin happens to be at the top left corner of the input, but is
 /// irrelevant because we use signed coordinates.
-type Elf = (isize, isize);
-type Tile = (isize, isize);
+pub type Elf = (isize, isize);
+pub type Tile = (isize, isize);
 
 /// Execute a proposed move for this elf, if this is the only elf that
 /// proposes to make this move, otherwise do nothing.
@@ -198,11 +210,11 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), 110);
+        assert_eq!(Day23::part1(&Day23::parse(INPUT).unwrap()), Ok(110));
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(INPUT), 20);
+        assert_eq!(Day23::part2(&Day23::parse(INPUT).unwrap()), Ok(20));
     }
 }
@@ -1,225 +1,326 @@
-use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use std::fmt;
 
-use fxhash::FxHashSet;
+use itertools::Itertools;
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/24.txt");
-    let (part1, part2) = both_parts(input);
-    format!("{part1}\n{part2}")
+use crate::{Error, Result, Solution};
+
+pub const INPUT: &str = include_str!("../inputs/24.txt");
+
+pub struct Day24;
+
+impl Solution for Day24 {
+    type Input = Valley;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    const DAY: u8 = 24;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        setup(input)
+    }
+
+    fn part1(valley: &Self::Input) -> Result<usize> {
+        valley.shortest_trip(&[valley.entrance, valley.exit], 0)
+    }
+
+    fn part2(valley: &Self::Input) -> Result<usize> {
+        let (entrance, exit) = (valley.entrance, valley.exit);
+        valley.shortest_trip(&[entrance, exit, entrance, exit], 0)
+    }
+}
+
+/// A position in the valley, as (x, y) on the map including its
+/// walls.
+pub type Tile = (usize, usize);
+
+/// The valley, and the blizzards blowing through it.
+#[derive(Debug)]
+pub struct Valley {
+    /// The size of the valley inside the walls.
+    height: usize,
+    width: usize,
+    pub entrance: Tile,
+    pub exit: Tile,
+    /// The walls on the map.
+    walls: BitSet,
+    /// The blizzards where they start, for replaying routes.
+    blizzards: Vec<Blizzard>,
+    /// For each row inside the walls, the starting x-positions of the
+    /// blizzards blowing right and left along it.
+    right: Vec<BitSet>,
+    left: Vec<BitSet>,
+    /// For each column inside the walls, the starting y-positions of
+    /// the blizzards blowing down and up along it.
+    down: Vec<BitSet>,
+    up: Vec<BitSet>,
 }
 
-fn both_parts(input: &str) -> (usize, usize) {
-    let (height, width, start_x, end_x, blizzard_positions) = setup(input);
-    let mut trip = 0;
-
-    // Leg 1
-    let mut visited: FxHashSet<(usize, (usize, isize))> = FxHashSet::default();
-    let mut queue: BinaryHeap<State> = BinaryHeap::from([State {
-        x: start_x,
-        y: -1,
-        time: trip,
-        reverse: false,
-    }]);
-    while let Some(state) = queue.pop() {
-        if let Some(distance) = state.step(
-            height,
-            width,
-            start_x,
-            end_x,
-            &mut queue,
-            &mut visited,
-            &blizzard_positions,
-        ) {
-            trip = distance;
-            break;
+impl Valley {
+    /// Finds the fastest way to visit each of the waypoints in turn,
+    /// leaving from the first one at a given time. Returns the time of
+    /// arrival at the last one.
+    pub fn shortest_trip(&self, waypoints: &[Tile], start_time: usize) -> Result<usize> {
+        Ok(self
+            .route(waypoints, start_time)?
+            .last()
+            .map_or(start_time, Route::end_time))
+    }
+
+    /// Finds the fastest way to visit each of the waypoints in turn,
+    /// leaving from the first one at a given time. Returns the route
+    /// taken for each leg.
+    pub fn route(&self, waypoints: &[Tile], start_time: usize) -> Result<Vec<Route>> {
+        if let Some(&(x, y)) = waypoints.iter().find(|&&(x, y)| !self.is_tile(x, y)) {
+            return Err(Error::new(format!("waypoint {x},{y} is not an open tile")));
         }
+        let mut time = start_time;
+        let mut routes = vec![];
+        for leg in waypoints.windows(2) {
+            let route = self.leg(leg[0], leg[1], time)?;
+            time = route.end_time();
+            routes.push(route);
+        }
+        Ok(routes)
     }
 
-    let part1 = trip;
-
-    // Leg 2 - back
-    let mut visited: FxHashSet<(usize, (usize, isize))> = FxHashSet::default();
-    let mut queue: BinaryHeap<State> = BinaryHeap::from([State {
-        x: end_x,
-        y: height as isize,
-        time: trip,
-        reverse: true,
-    }]);
-    while let Some(state) = queue.pop() {
-        if let Some(distance) = state.step(
-            height,
-            width,
-            start_x,
-            end_x,
-            &mut queue,
-            &mut visited,
-            &blizzard_positions,
-        ) {
-            trip = distance;
-            break;
+    /// Finds the fastest way from one tile to another, leaving at a
+    /// given time.
+    fn leg(&self, from: Tile, to: Tile, start_time: usize) -> Result<Route> {
+        // The blizzards are back where they started after this long,
+        // so being on a tile at a time in the cycle is the same as
+        // being there at that time in any other cycle.
+        let period = lcm(self.width, self.height);
+        // Keyed by time first, so the set only grows as far as the
+        // search gets.
+        let tiles = (self.width + 2) * (self.height + 2);
+        let state = |(x, y): Tile, time: usize| time % period * tiles + y * (self.width + 2) + x;
+        let mut seen = BitSet::default();
+        seen.insert(state(from, start_time));
+
+        // The tiles reached each minute, with the index of the tile
+        // they were reached from in the minute before, and the move
+        // made to get there.
+        let mut minutes = vec![vec![(from, 0, Move::Wait)]];
+        let mut time = start_time;
+        while let Some(positions) = minutes.last().filter(|p| !p.is_empty()) {
+            if let Some(mut i) = positions.iter().position(|&(tile, ..)| tile == to) {
+                let mut moves = vec![];
+                for positions in minutes[1..].iter().rev() {
+                    let (_, previous, step) = positions[i];
+                    moves.push(step);
+                    i = previous;
+                }
+                moves.reverse();
+                return Ok(Route {
+                    start: from,
+                    start_time,
+                    moves,
+                });
+            }
+            time += 1;
+            let mut next = vec![];
+            for (i, &(tile, ..)) in positions.iter().enumerate() {
+                for step in Move::ALL {
+                    let tile = step.apply(tile);
+                    if self.is_open(tile, time) && seen.insert(state(tile, time)) {
+                        next.push((tile, i, step));
+                    }
+                }
+            }
+            minutes.push(next);
         }
+        Err(Error::new("no route found"))
     }
 
-    // Leg 3 - there again
-    let mut visited: FxHashSet<(usize, (usize, isize))> = FxHashSet::default();
-    let mut queue: BinaryHeap<State> = BinaryHeap::from([State {
-        x: start_x,
-        y: -1,
-        time: trip,
-        reverse: false,
-    }]);
-    while let Some(state) = queue.pop() {
-        if let Some(distance) = state.step(
-            height,
-            width,
-            start_x,
-            end_x,
-            &mut queue,
-            &mut visited,
-            &blizzard_positions,
-        ) {
-            trip = distance;
-            break;
+    /// Replays a route, moving the blizzards one by one, and checks
+    /// that it stays on the map and out of the storms.
+    pub fn check_route(&self, route: &Route) -> Result<()> {
+        let mut tile = route.start;
+        for (time, step) in (route.start_time + 1..).zip(&route.moves) {
+            tile = step.apply(tile);
+            let (x, y) = tile;
+            if !self.is_tile(x, y) {
+                return Err(Error::new(format!(
+                    "route leaves the valley at {x},{y} in minute {time}"
+                )));
+            }
+            let mut blizzards = self
+                .blizzards
+                .iter()
+                .map(|b| b.position_at(time, self.height, self.width));
+            if blizzards.any(|(bx, by)| (bx + 1, by + 1) == tile) {
+                return Err(Error::new(format!(
+                    "route runs into a blizzard at {x},{y} in minute {time}"
+                )));
+            }
         }
+        Ok(())
+    }
+
+    /// Returns true if a position is on the map, and not a wall.
+    fn is_tile(&self, x: usize, y: usize) -> bool {
+        x < self.width + 2 && y < self.height + 2 && !self.walls.contains(y * (self.width + 2) + x)
     }
 
-    (part1, trip)
+    /// Returns true if a tile is free of blizzards at a given time.
+    fn is_open(&self, (x, y): Tile, time: usize) -> bool {
+        if !self.is_tile(x, y) {
+            return false;
+        }
+        // The entrance and exit are out of reach of the blizzards.
+        if !(1..=self.width).contains(&x) || !(1..=self.height).contains(&y) {
+            return true;
+        }
+        let (x, y) = (x - 1, y - 1);
+        let (w, h) = (self.width, self.height);
+        let (dx, dy) = (time % w, time % h);
+        !(self.right[y].contains((x + w - dx) % w)
+            || self.left[y].contains((x + dx) % w)
+            || self.down[x].contains((y + h - dy) % h)
+            || self.up[x].contains((y + dy) % h))
+    }
 }
 
-/// Parses the input and returns:
-/// - the height and width of the usable map
-/// - the x-coordinate of the start and end locations
-/// - a Vec of bitmasks for each cell, discribing if there will be a storm
-fn setup(input: &str) -> (usize, usize, usize, usize, Vec<[u128; 8]>) {
-    let height = input.lines().count() - 2;
-    let width = input.lines().next().unwrap().chars().count() - 2;
-    let start_x = input.chars().skip(1).position(|c| c != '#').unwrap();
-    let end_x = width - input.chars().rev().skip(1).position(|c| c != '#').unwrap();
-    let blizzards: Vec<Blizzard> = input
-        .lines()
-        .skip(1)
-        .take(height)
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.chars()
-                .skip(1)
-                .take(width)
-                .enumerate()
-                .filter_map(|(x, c)| {
-                    if c == '.' {
-                        return None;
-                    }
-                    let heading = match c {
-                        '>' => Heading::Right,
-                        '<' => Heading::Left,
-                        '^' => Heading::Up,
-                        'v' => Heading::Down,
-                        _ => panic!("invalid tile"),
-                    };
-                    Some(Blizzard { x, y, heading })
-                })
-                .collect::<Vec<_>>()
-        })
-        .collect();
-    let mut blizzard_positions: Vec<[u128; 8]> = (0..height * width)
-        .map(|_| [0, 0, 0, 0, 0, 0, 0, 0])
-        .collect();
-    for blizzard in blizzards {
-        (0..128 * 8).for_each(|time| {
-            let (x, y) = blizzard.position_at(time, height, width);
-            let idx = y * width + x;
-            let bitmask = time / 128;
-            let offset = time % 128;
-            blizzard_positions[idx][bitmask] |= 1 << offset;
-        });
+/// Parses the input and records where the blizzards start.
+fn setup(input: &str) -> Result<Valley> {
+    let lines: Vec<_> = input.lines().collect();
+    let too_small = || Error::new("valley is too small");
+    let height = lines.len().checked_sub(2).ok_or_else(too_small)?;
+    let width = lines[0]
+        .chars()
+        .count()
+        .checked_sub(2)
+        .ok_or_else(too_small)?;
+    if height == 0 || width == 0 {
+        return Err(too_small());
+    }
+    let entrance = (
+        lines[0]
+            .find('.')
+            .ok_or(Error::new("unable to find entrance"))?,
+        0,
+    );
+    let exit = (
+        lines[height + 1]
+            .find('.')
+            .ok_or(Error::new("unable to find exit"))?,
+        height + 1,
+    );
+
+    let mut walls = BitSet::default();
+    let mut blizzards = vec![];
+    let mut right = vec![BitSet::default(); height];
+    let mut left = vec![BitSet::default(); height];
+    let mut down = vec![BitSet::default(); width];
+    let mut up = vec![BitSet::default(); width];
+    for (y, line) in lines.iter().enumerate() {
+        let tiles: Vec<_> = line.char_indices().take(width + 2).collect();
+        if tiles.len() < width + 2 {
+            return Err(Error::new("valley is not rectangular").located(input, line));
+        }
+        for (x, (i, c)) in tiles.into_iter().enumerate() {
+            let inside = (1..=width).contains(&x) && (1..=height).contains(&y);
+            let (bx, by) = (x.wrapping_sub(1), y.wrapping_sub(1));
+            let heading = match c {
+                '#' if !inside => {
+                    walls.insert(y * (width + 2) + x);
+                    continue;
+                }
+                '.' => continue,
+                '>' if inside => Heading::Right,
+                '<' if inside => Heading::Left,
+                'v' if inside => Heading::Down,
+                '^' if inside => Heading::Up,
+                _ => return Err(Error::new("invalid tile").located(input, &line[i..])),
+            };
+            match heading {
+                Heading::Right => right[by].insert(bx),
+                Heading::Left => left[by].insert(bx),
+                Heading::Down => down[bx].insert(by),
+                Heading::Up => up[bx].insert(by),
+            };
+            blizzards.push(Blizzard {
+                x: bx,
+                y: by,
+                heading,
+            });
+        }
     }
-    (height, width, start_x, end_x, blizzard_positions)
+    Ok(Valley {
+        height,
+        width,
+        entrance,
+        exit,
+        walls,
+        blizzards,
+        right,
+        left,
+        down,
+        up,
+    })
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
-struct State {
-    x: usize,
-    y: isize,
-    time: usize,
-    reverse: bool,
+/// One minute's move on a route through the valley.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Move {
+    Wait,
+    North,
+    South,
+    East,
+    West,
 }
 
-impl State {
-    #[allow(clippy::too_many_arguments)]
-    /// Takes a step during BFS pathfinding, returns `Some(distance)`
-    /// if it reaches the goal.
-    fn step(
-        &self,
-        height: usize,
-        width: usize,
-        start_x: usize,
-        end_x: usize,
-        queue: &mut BinaryHeap<Self>,
-        visited: &mut FxHashSet<(usize, (usize, isize))>,
-        blizzard_positions: &[[u128; 8]],
-    ) -> Option<usize> {
-        if (!self.reverse && self.x == end_x && self.y == height as isize - 1)
-            || (self.reverse && self.x == start_x && self.y == 0)
-        {
-            return Some(self.time);
-        }
+impl Move {
+    const ALL: [Self; 5] = [Self::South, Self::East, Self::Wait, Self::North, Self::West];
 
-        if visited.contains(&(self.time, (self.x, self.y))) {
-            return None;
-        } else {
-            visited.insert((self.time, (self.x, self.y)));
+    /// Returns the tile this move leads to. Moving off the top or left
+    /// of the map leads to a tile that is far out of the valley.
+    fn apply(self, (x, y): Tile) -> Tile {
+        match self {
+            Self::Wait => (x, y),
+            Self::North => (x, y.wrapping_sub(1)),
+            Self::South => (x, y + 1),
+            Self::East => (x + 1, y),
+            Self::West => (x.wrapping_sub(1), y),
         }
+    }
+}
 
-        [
-            (self.x, (self.y + 1).min(height as isize - 1)),
-            ((self.x + 1).min(width - 1), self.y),
-            (self.x, self.y),
-            (self.x, (self.y - 1).max(0)),
-            (self.x.saturating_sub(1), self.y),
-        ]
-        .iter()
-        .copied()
-        .filter(|(x, y)| {
-            // Allow waiting outside the grid in the starting position.
-            (!self.reverse && (*y >= 0 || *x == start_x))
-                || (self.reverse && (*y < height as isize || *x == end_x))
-        })
-        .filter(|(x, y)| {
-            (*y < 0 && !self.reverse)
-                || (*y == height as isize && self.reverse)
-                || 0 == (blizzard_positions[*y as usize * width + x][self.time / 128]
-                    & 1 << (self.time % 128))
-        })
-        .for_each(|opt| {
-            let mut new_self = *self;
-            new_self.time += 1;
-            (new_self.x, new_self.y) = opt;
-            queue.push(new_self);
-        });
-        None
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Wait => "wait",
+            Self::North => "N",
+            Self::South => "S",
+            Self::East => "E",
+            Self::West => "W",
+        };
+        write!(f, "{s}")
     }
 }
 
-impl PartialOrd for State {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+/// The moves made on one leg of a trip, one per minute.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Route {
+    pub start: Tile,
+    pub start_time: usize,
+    pub moves: Vec<Move>,
+}
+
+impl Route {
+    pub fn end_time(&self) -> usize {
+        self.start_time + self.moves.len()
+    }
+
+    /// Returns the tile where the route ends.
+    pub fn end(&self) -> Tile {
+        self.moves.iter().fold(self.start, |tile, m| m.apply(tile))
     }
 }
 
-impl Ord for State {
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self.reverse {
-            other
-                .time
-                .cmp(&self.time)
-                .then_with(|| (other.x as isize + other.y).cmp(&(self.x as isize + self.y)))
-        } else {
-            other
-                .time
-                .cmp(&self.time)
-                .then_with(|| (self.x as isize + self.y).cmp(&(other.x as isize + other.y)))
-        }
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.moves.iter().join(" "))
     }
 }
 
@@ -239,7 +340,8 @@ enum Heading {
 }
 
 impl Blizzard {
-    /// Returns the position of this blizzard at a given time.
+    /// Returns the position of this blizzard at a given time, inside
+    /// the walls.
     fn position_at(&self, time: usize, height: usize, width: usize) -> (usize, usize) {
         match self.heading {
             Heading::Left => (((time / width + 1) * width + self.x - time) % width, self.y),
@@ -253,6 +355,43 @@ impl Blizzard {
     }
 }
 
+/// A set of small numbers, which grows to fit whatever is inserted.
+#[derive(Clone, Debug, Default)]
+struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    fn contains(&self, n: usize) -> bool {
+        self.words
+            .get(n / 64)
+            .is_some_and(|word| word & (1 << (n % 64)) != 0)
+    }
+
+    /// Adds a number to the set. Returns true if it was not in the set
+    /// already.
+    fn insert(&mut self, n: usize) -> bool {
+        if n / 64 >= self.words.len() {
+            self.words.resize(n / 64 + 1, 0);
+        }
+        let word = &mut self.words[n / 64];
+        let bit = 1 << (n % 64);
+        let new = *word & bit == 0;
+        *word |= bit;
+        new
+    }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    let gcd = |mut a: usize, mut b: usize| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    a / gcd(a, b) * b
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -267,11 +406,88 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(both_parts(INPUT).0, 18);
+        assert_eq!(Day24::part1(&Day24::parse(INPUT).unwrap()), Ok(18));
     }
 
     #[test]
     fn part2_example() {
-        assert_eq!(both_parts(INPUT).1, 54);
+        assert_eq!(Day24::part2(&Day24::parse(INPUT).unwrap()), Ok(54));
+    }
+
+    #[test]
+    fn routes() {
+        let valley = Day24::parse(INPUT).unwrap();
+        let (entrance, exit) = (valley.entrance, valley.exit);
+        let routes = valley.route(&[entrance, exit, entrance, exit], 0).unwrap();
+        let ends: Vec<_> = routes.iter().map(|r| (r.end(), r.end_time())).collect();
+        assert_eq!(ends, [(exit, 18), (entrance, 41), (exit, 54)]);
+        for route in &routes {
+            assert_eq!(valley.check_route(route), Ok(()));
+        }
+    }
+
+    #[test]
+    fn check_route() {
+        let valley = Day24::parse(INPUT).unwrap();
+        let mut route = valley
+            .route(&[valley.entrance, valley.exit], 0)
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            route.to_string(),
+            "S S wait N E E S W N E wait S S E E E S S"
+        );
+        assert_eq!(route.end(), valley.exit);
+        assert_eq!(valley.check_route(&route), Ok(()));
+
+        route.moves[2] = Move::South;
+        assert_eq!(
+            valley.check_route(&route),
+            Err(Error::new("route runs into a blizzard at 1,3 in minute 3"))
+        );
+        route.moves[0] = Move::East;
+        assert_eq!(
+            valley.check_route(&route),
+            Err(Error::new("route leaves the valley at 2,0 in minute 1"))
+        );
+    }
+
+    #[test]
+    fn waypoints() {
+        let valley = Day24::parse(INPUT).unwrap();
+        let (entrance, exit) = (valley.entrance, valley.exit);
+        assert_eq!((entrance, exit), ((1, 0), (6, 5)));
+        // The legs of part 2, one at a time.
+        assert_eq!(valley.shortest_trip(&[entrance, exit], 0), Ok(18));
+        assert_eq!(valley.shortest_trip(&[exit, entrance], 18), Ok(41));
+        assert_eq!(valley.shortest_trip(&[entrance, exit], 41), Ok(54));
+        assert_eq!(valley.shortest_trip(&[entrance], 7), Ok(7));
+        assert_eq!(
+            valley.shortest_trip(&[entrance, (0, 1)], 0),
+            Err(Error::new("waypoint 0,1 is not an open tile"))
+        );
+    }
+
+    #[test]
+    fn large_valleys() {
+        // Too wide or too tall for a blizzard schedule in a u128. With
+        // no blizzards, the way through is the direct one.
+        for (width, height) in [(200, 3), (3, 200)] {
+            let mut input = format!("#.{}\n", "#".repeat(width));
+            for _ in 0..height {
+                input += &format!("#{}#\n", ".".repeat(width));
+            }
+            input += &format!("{}.#\n", "#".repeat(width));
+            let valley = Day24::parse(&input).unwrap();
+            assert_eq!(valley.exit, (width, height + 1));
+            assert_eq!(Day24::part1(&valley), Ok(width + height));
+        }
+    }
+
+    #[test]
+    fn no_route() {
+        // A blizzard blows through the only way out, every minute.
+        let valley = Day24::parse("#.#\n#v#\n#.#").unwrap();
+        assert_eq!(Day24::part1(&valley), Err(Error::new("no route found")));
     }
 }
@@ -1,68 +1,80 @@
-use itertools::iterate;
+use crate::error::parse_lines;
+use crate::{Error, NoAnswer, Result, Solution};
 
-pub fn solve() -> String {
-    let input = include_str!("../inputs/25.txt");
-    format!("{}", part1(input))
-}
+pub const INPUT: &str = include_str!("../inputs/25.txt");
+
+pub struct Day25;
+
+impl Solution for Day25 {
+    /// The fuel requirements, converted to decimal.
+    type Input = Vec<i64>;
+    /// The sum of all requirements, in SNAFU.
+    type Part1 = String;
+    type Part2 = NoAnswer;
+
+    const DAY: u8 = 25;
+    const HAS_PART2: bool = false;
+
+    fn parse(input: &str) -> Result<Self::Input> {
+        parse_lines(input, snafu_to_decimal)
+    }
 
-fn part1(input: &str) -> String {
-    decimal_to_snafu(input.lines().map(snafu_to_decimal).sum())
+    fn part1(requirements: &Self::Input) -> Result<String> {
+        let total = requirements
+            .iter()
+            .try_fold(0i64, |total, &n| total.checked_add(n))
+            .ok_or_else(|| Error::new("total fuel requirement overflows"))?;
+        if total < 0 {
+            return Err(Error::new("total fuel requirement is negative"));
+        }
+        Ok(decimal_to_snafu(total))
+    }
+
+    fn part2(_: &Self::Input) -> Result<NoAnswer> {
+        Ok(NoAnswer)
+    }
 }
 
 /// Takes a number in SNAFU format and returns a decimal number.
-fn snafu_to_decimal(i: &str) -> i64 {
-    let places = iterate(1, |n| n * 5);
-    let digit_value = |c| match c {
-        '=' => -2,
-        '-' => -1,
-        '0' => 0,
-        '1' => 1,
-        '2' => 2,
-        _ => panic!("invalid SNAFU digit"),
-    };
-    i.chars()
-        .rev()
-        .zip(places)
-        .map(|(c, value)| value * digit_value(c))
-        .sum()
+pub fn snafu_to_decimal(i: &str) -> Result<i64> {
+    i.char_indices().try_fold(0i64, |value, (idx, c)| {
+        let digit = match c {
+            '=' => -2,
+            '-' => -1,
+            '0' => 0,
+            '1' => 1,
+            '2' => 2,
+            _ => return Err(Error::new("invalid SNAFU digit").located(i, &i[idx..])),
+        };
+        value
+            .checked_mul(5)
+            .and_then(|value| value.checked_add(digit))
+            .ok_or_else(|| Error::new("SNAFU number overflows").located(i, &i[idx..]))
+    })
 }
 
 /// Takes a decimal number and returns a number in SNAFU format. Only
 /// handles integers >= 0.
-fn decimal_to_snafu(i: i64) -> String {
+pub fn decimal_to_snafu(mut i: i64) -> String {
     if i == 0 {
         return "0".to_string();
     }
-    // Find the top digit.
-    let top_place = iterate(1, |n| n * 5)
-        .take_while(|n| i * 2 >= *n)
-        .last()
-        .unwrap();
-    let digit = |n| match n {
-        -2 => '=',
-        -1 => '-',
-        0 => '0',
-        1 => '1',
-        2 => '2',
-        _ => panic!("oh no"),
-    };
-    let first_digit = (1..=2).min_by_key(|n| i.abs_diff(n * top_place)).unwrap();
-    let mut rv = String::from(digit(first_digit));
-    if top_place == 1 {
-        return rv;
-    }
-    let mut current = top_place * first_digit;
-    for place in iterate(top_place / 5, |n| n / 5) {
-        let next_digit = (-2..=2)
-            .min_by_key(|n| (current + n * place).abs_diff(i))
-            .unwrap();
-        current += next_digit * place;
-        rv.push(digit(next_digit));
-        if place <= 1 {
-            break;
-        }
+    // Work up from the bottom digit. A remainder of 3 or 4 is a digit
+    // of -2 or -1 with one carried to the next place, which can't
+    // overflow as the rest has already been divided by 5.
+    let mut digits = vec![];
+    while i > 0 {
+        let (digit, carry) = match i % 5 {
+            0 => ('0', 0),
+            1 => ('1', 0),
+            2 => ('2', 0),
+            3 => ('=', 1),
+            _ => ('-', 1),
+        };
+        digits.push(digit);
+        i = i / 5 + carry;
     }
-    rv
+    digits.iter().rev().collect()
 }
 
 #[cfg(test)]
@@ -86,6 +98,42 @@ mod tests {
 
     #[test]
     fn part1_example() {
-        assert_eq!(part1(INPUT), "2=-1=0");
+        assert_eq!(
+            Day25::part1(&Day25::parse(INPUT).unwrap()),
+            Ok("2=-1=0".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_digit() {
+        assert_eq!(
+            Day25::parse("1=-0-2\n12311\n").unwrap_err().to_string(),
+            "line 2, column 3: invalid SNAFU digit"
+        );
+    }
+
+    #[test]
+    fn conversions() {
+        for n in [0, 1, 2, 3, 4, 5, 2022, 12345, 314159265, i64::MAX] {
+            assert_eq!(snafu_to_decimal(&decimal_to_snafu(n)), Ok(n));
+        }
+        assert_eq!(decimal_to_snafu(2022), "1=11-2");
+        assert_eq!(decimal_to_snafu(314159265), "1121-1110-1=0");
+        assert_eq!(snafu_to_decimal("-2"), Ok(-3));
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(
+            Day25::parse(&format!("1\n{}\n", "2".repeat(30)))
+                .unwrap_err()
+                .to_string(),
+            "line 2, column 28: SNAFU number overflows"
+        );
+        let max = decimal_to_snafu(i64::MAX);
+        assert_eq!(
+            Day25::part1(&Day25::parse(&format!("{max}\n1\n")).unwrap()),
+            Err(Error::new("total fuel requirement overflows"))
+        );
     }
 }
@@ -0,0 +1,153 @@
+use std::fmt;
+
+use nom::IResult;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// An error encountered while parsing an input or solving a puzzle.
+/// Locations are 1-based, and relative to the whole input.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Error {
+    pub day: Option<u8>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl Error {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            day: None,
+            line: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    /// Returns the same error, located at a position in the input.
+    pub fn at(mut self, line: usize, column: usize) -> Self {
+        self.line = Some(line);
+        self.column = Some(column);
+        self
+    }
+
+    /// Returns the same error, attributed to a day.
+    pub fn on_day(mut self, day: u8) -> Self {
+        self.day = Some(day);
+        self
+    }
+
+    /// Returns the same error, located at the start of `at`, which
+    /// must be a slice of `input`.
+    pub(crate) fn located(self, input: &str, at: &str) -> Self {
+        let offset = (at.as_ptr() as usize).wrapping_sub(input.as_ptr() as usize);
+        if offset > input.len() {
+            return self;
+        }
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        self.at(
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        )
+    }
+
+    /// Translates the location of an error relative to `part`, which
+    /// must be a slice of `input`, into one relative to `input`.
+    /// Errors without a location are located at the start of `part`.
+    pub(crate) fn within(self, input: &str, part: &str) -> Self {
+        let outer = Self::new("").located(input, part);
+        match (self.line, self.column, outer.line, outer.column) {
+            (Some(line), Some(column), Some(outer_line), Some(outer_column)) => {
+                let column = if line == 1 {
+                    column + outer_column - 1
+                } else {
+                    column
+                };
+                self.at(line + outer_line - 1, column)
+            }
+            (Some(line), None, Some(outer_line), _) => Self {
+                line: Some(line + outer_line - 1),
+                ..self
+            },
+            (None, _, Some(outer_line), Some(outer_column)) => self.at(outer_line, outer_column),
+            _ => self,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location: Vec<String> = [
+            self.day.map(|day| format!("day {day}")),
+            self.line.map(|line| format!("line {line}")),
+            self.column.map(|column| format!("column {column}")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if location.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", location.join(", "), self.message)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Parses every line of the input, locating any errors at the line
+/// they occurred on.
+pub(crate) fn parse_lines<'a, T>(
+    input: &'a str,
+    mut parse: impl FnMut(&'a str) -> Result<T>,
+) -> Result<Vec<T>> {
+    input
+        .lines()
+        .map(|line| parse(line).map_err(|e| e.within(input, line)))
+        .collect()
+}
+
+/// Adapts a nom parser to consume a whole string, returning an error
+/// located where parsing failed, with a message describing what was
+/// being parsed.
+pub(crate) fn nom<'a, O>(
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+    what: &'static str,
+) -> impl FnMut(&'a str) -> Result<O> {
+    move |i| match parser(i) {
+        Ok(("", o)) => Ok(o),
+        Ok((rest, _)) => Err(Error::new(format!("unexpected input after {what}")).located(i, rest)),
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+            Err(Error::new(format!("failed to parse {what}")).located(i, e.input))
+        }
+        Err(nom::Err::Incomplete(_)) => Err(Error::new(format!("incomplete {what}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use nom::{bytes::complete::tag, character::complete::u8, sequence::tuple};
+
+    #[test]
+    fn locate_in_input() {
+        let input = "abc\ndef\nghi";
+        let e = Error::new("oops").located(input, &input[5..]);
+        assert_eq!((e.line, e.column), (Some(2), Some(2)));
+        assert_eq!(e.on_day(3).to_string(), "day 3, line 2, column 2: oops");
+    }
+
+    #[test]
+    fn locate_nom_errors_in_lines() {
+        let input = "1,2\n3,x\n";
+        let e = parse_lines(input, nom(tuple((u8, tag(","), u8)), "pair")).unwrap_err();
+        assert_eq!(e.to_string(), "line 2, column 3: failed to parse pair");
+        let e = parse_lines("1,23 ", nom(tuple((u8, tag(","), u8)), "pair")).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "line 1, column 5: unexpected input after pair"
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! Just enough JSON to write the runner's reports, and read back the
+//! ones it wrote.
+
+use std::fmt;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{escaped_transform, is_not, tag, take_while_m_n},
+    character::complete::{char, multispace0},
+    combinator::{all_consuming, map, map_opt, map_res, value, verify},
+    multi::separated_list0,
+    number::complete::double,
+    sequence::{delimited, preceded, separated_pair},
+    IResult,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// Keys in the order they were inserted.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Returns the value of a key, if this is an object that has it.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Self::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Parses a JSON document.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        all_consuming(delimited(multispace0, parse_value, multispace0))(s)
+            .map(|(_, v)| v)
+            .map_err(|e| format!("invalid JSON: {e}"))
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Self::Number(n)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(n: usize) -> Self {
+        Self::Number(n as f64)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map(Into::into).unwrap_or(Self::Null)
+    }
+}
+
+/// Writes compact JSON. The alternate flag puts each element of the
+/// outermost array or object on its own line.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let separator = if f.alternate() { ",\n" } else { "," };
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::Number(n) => write!(f, "{n}"),
+            Self::String(s) => write_string(f, s),
+            Self::Array(values) => {
+                write!(f, "[")?;
+                for (i, v) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{separator}")?;
+                    }
+                    write!(f, "{v}")?;
+                }
+                write!(f, "]")
+            }
+            Self::Object(entries) => {
+                write!(f, "{{")?;
+                for (i, (k, v)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{separator}")?;
+                    }
+                    write_string(f, k)?;
+                    write!(f, ":{v}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Parses a value, and any whitespace following it.
+fn parse_value(i: &str) -> IResult<&str, Value> {
+    let (i, v) = alt((
+        value(Value::Null, tag("null")),
+        value(Value::Bool(true), tag("true")),
+        value(Value::Bool(false), tag("false")),
+        map(double, Value::Number),
+        map(parse_string, Value::String),
+        map(
+            delimited(
+                pair_tag("["),
+                separated_list0(pair_tag(","), parse_value),
+                tag("]"),
+            ),
+            Value::Array,
+        ),
+        map(
+            delimited(
+                pair_tag("{"),
+                separated_list0(
+                    pair_tag(","),
+                    separated_pair(parse_string, pair_tag(":"), parse_value),
+                ),
+                tag("}"),
+            ),
+            Value::Object,
+        ),
+    ))(i)?;
+    let (i, _) = multispace0(i)?;
+    Ok((i, v))
+}
+
+/// Matches a tag surrounded by optional whitespace.
+fn pair_tag<'a>(t: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    delimited(multispace0, tag(t), multispace0)
+}
+
+/// Parses a string, without support for unicode escapes.
+fn parse_string(i: &str) -> IResult<&str, String> {
+    let (i, s) = preceded(
+        char('"'),
+        alt((
+            escaped_transform(
+                is_not("\"\\"),
+                '\\',
+                alt((
+                    value('"', tag("\"")),
+                    value('\\', tag("\\")),
+                    value('/', tag("/")),
+                    value('\u{8}', tag("b")),
+                    value('\u{c}', tag("f")),
+                    value('\n', tag("n")),
+                    value('\r', tag("r")),
+                    value('\t', tag("t")),
+                    preceded(char('u'), parse_code_point),
+                )),
+            ),
+            map(tag(""), str::to_string),
+        )),
+    )(i)?;
+    let (i, _) = char('"')(i)?;
+    Ok((i, s))
+}
+
+/// Parses the hex digits of a `\u` escape. Characters outside the
+/// basic multilingual plane are escaped as a surrogate pair, the
+/// second half of which follows as another escape.
+fn parse_code_point(i: &str) -> IResult<&str, char> {
+    let hex = || {
+        map_res(take_while_m_n(4, 4, |c: char| c.is_ascii_hexdigit()), |h| {
+            u32::from_str_radix(h, 16)
+        })
+    };
+    let (rest, high) = hex()(i)?;
+    if !(0xd800..0xdc00).contains(&high) {
+        return map_opt(hex(), char::from_u32)(i);
+    }
+    map_opt(
+        preceded(
+            tag("\\u"),
+            verify(hex(), |low| (0xdc00..0xe000).contains(low)),
+        ),
+        move |low| char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)),
+    )(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let v = Value::Object(vec![
+            ("day".to_string(), 10usize.into()),
+            ("answer".to_string(), "#..#\n\"ok\"".into()),
+            ("duration".to_string(), Some(1.5).into()),
+            ("missing".to_string(), None::<f64>.into()),
+            (
+                "parts".to_string(),
+                Value::Array(vec![Value::Bool(true), Value::Array(vec![])]),
+            ),
+        ]);
+        let json = v.to_string();
+        assert_eq!(
+            json,
+            r##"{"day":10,"answer":"#..#\n\"ok\"","duration":1.5,"missing":null,"parts":[true,[]]}"##
+        );
+        assert_eq!(Value::parse(&json), Ok(v.clone()));
+        assert_eq!(Value::parse(&format!("{v:#}")), Ok(v));
+    }
+
+    #[test]
+    fn parse_whitespace_and_errors() {
+        let v = Value::parse(" { \"a\" : [ 1 , 2 ] , \"b\" : \"\" } ").unwrap();
+        assert_eq!(
+            v.get("a").and_then(Value::as_array).map(<[_]>::len),
+            Some(2)
+        );
+        assert_eq!(v.get("b"), Some(&Value::String(String::new())));
+        assert!(Value::parse("{\"a\": }").is_err());
+        assert!(Value::parse("[1, 2").is_err());
+    }
+
+    #[test]
+    fn unicode_escapes() {
+        for s in ["\u{1}", "a\u{1f}b\u{7f}", "\u{1F600}"] {
+            let v = Value::String(s.to_string());
+            assert_eq!(Value::parse(&v.to_string()), Ok(v));
+        }
+        let string = |s: &str| Ok(Value::String(s.to_string()));
+        assert_eq!(
+            Value::parse(r#""\u0041\u00e9\ud83d\ude00""#),
+            string("A\u{e9}\u{1F600}")
+        );
+        assert_eq!(Value::parse(r#""\b\f""#), string("\u{8}\u{c}"));
+        // Halves of surrogate pairs don't stand on their own.
+        assert!(Value::parse(r#""\ud83d""#).is_err());
+        assert!(Value::parse(r#""\ude00""#).is_err());
+        assert!(Value::parse(r#""\ud83d\u0041""#).is_err());
+        assert!(Value::parse(r#""\u12""#).is_err());
+    }
+}
@@ -0,0 +1,247 @@
+//! Solutions to Advent of Code 2022. Every day implements
+//! [`Solution`], and [`DAYS`] lists them all in order, along with
+//! the inputs they were solved for.
+
+use std::fmt::Display;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+mod error;
+
+pub use error::{Error, Result};
+
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day25;
+
+/// The half of a day's puzzle to solve.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Part {
+    One,
+    Two,
+}
+
+/// A day's puzzle. The input is parsed once and shared by both
+/// parts.
+pub trait Solution {
+    /// The parsed puzzle input.
+    type Input;
+    type Part1: Display;
+    type Part2: Display;
+
+    /// The day of the puzzle, attached to errors.
+    const DAY: u8;
+    /// Whether there is a second part. Only the last day has a single
+    /// part, the second star is awarded for free.
+    const HAS_PART2: bool = true;
+
+    fn parse(input: &str) -> Result<Self::Input>;
+    fn part1(input: &Self::Input) -> Result<Self::Part1>;
+    fn part2(input: &Self::Input) -> Result<Self::Part2>;
+}
+
+/// The answer to a part that does not exist.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct NoAnswer;
+
+impl Display for NoAnswer {
+    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Ok(())
+    }
+}
+
+/// The answer to one part of a day, as it would be submitted.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Answer {
+    pub part: Part,
+    pub answer: Result<String>,
+    /// How long solving the part took. The first part solved also
+    /// counts the time it took to parse the input.
+    pub duration: Duration,
+}
+
+/// Parses the input once and solves each of the given parts with
+/// it. Parts the day does not have are left out.
+pub fn solve<S: Solution>(input: &str, parts: &[Part]) -> Vec<Answer> {
+    let mut start = Instant::now();
+    let parsed = S::parse(input);
+    parts
+        .iter()
+        .filter(|&&part| part == Part::One || S::HAS_PART2)
+        .map(|&part| {
+            let answer = match (&parsed, part) {
+                (Err(e), _) => Err(e.clone()),
+                (Ok(input), Part::One) => S::part1(input).map(|a| a.to_string()),
+                (Ok(input), Part::Two) => S::part2(input).map(|a| a.to_string()),
+            };
+            let duration = start.elapsed();
+            start = Instant::now();
+            Answer {
+                part,
+                answer: answer.map_err(|e| e.on_day(S::DAY)),
+                duration,
+            }
+        })
+        .collect()
+}
+
+/// How long each phase of solving a day took.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Timings {
+    pub parse: Duration,
+    pub part1: Duration,
+    /// None if the day does not have a second part.
+    pub part2: Option<Duration>,
+}
+
+/// Parses the input and solves both parts, timing each phase
+/// separately.
+pub fn time<S: Solution>(input: &str) -> Result<Timings> {
+    let timings = || -> Result<Timings> {
+        let start = Instant::now();
+        let input = black_box(S::parse(input)?);
+        let parse = start.elapsed();
+        let start = Instant::now();
+        black_box(S::part1(&input)?);
+        let part1 = start.elapsed();
+        let part2 = if S::HAS_PART2 {
+            let start = Instant::now();
+            black_box(S::part2(&input)?);
+            Some(start.elapsed())
+        } else {
+            None
+        };
+        Ok(Timings {
+            parse,
+            part1,
+            part2,
+        })
+    };
+    timings().map_err(|e| e.on_day(S::DAY))
+}
+
+/// Solves parts of a day for a given input, see [`solve`].
+pub type Solver = fn(&str, &[Part]) -> Vec<Answer>;
+
+/// Times solving a day for a given input, see [`time`].
+pub type Timer = fn(&str) -> Result<Timings>;
+
+/// A day's solution with its types erased, so that all days can be
+/// listed together.
+#[derive(Copy, Clone)]
+pub struct Day {
+    pub solve: Solver,
+    pub time: Timer,
+    /// The input embedded at compile time.
+    pub input: &'static str,
+}
+
+impl Day {
+    const fn new<S: Solution>(input: &'static str) -> Self {
+        Self {
+            solve: solve::<S>,
+            time: time::<S>,
+            input,
+        }
+    }
+}
+
+/// Every day's solution, along with its embedded input.
+pub const DAYS: [Day; 25] = [
+    Day::new::<day01::Day01>(day01::INPUT),
+    Day::new::<day02::Day02>(day02::INPUT),
+    Day::new::<day03::Day03>(day03::INPUT),
+    Day::new::<day04::Day04>(day04::INPUT),
+    Day::new::<day05::Day05>(day05::INPUT),
+    Day::new::<day06::Day06>(day06::INPUT),
+    Day::new::<day07::Day07>(day07::INPUT),
+    Day::new::<day08::Day08>(day08::INPUT),
+    Day::new::<day09::Day09>(day09::INPUT),
+    Day::new::<day10::Day10>(day10::INPUT),
+    Day::new::<day11::Day11>(day11::INPUT),
+    Day::new::<day12::Day12>(day12::INPUT),
+    Day::new::<day13::Day13>(day13::INPUT),
+    Day::new::<day14::Day14>(day14::INPUT),
+    Day::new::<day15::Day15>(day15::INPUT),
+    Day::new::<day16::Day16>(day16::INPUT),
+    Day::new::<day17::Day17>(day17::INPUT),
+    Day::new::<day18::Day18>(day18::INPUT),
+    Day::new::<day19::Day19>(day19::INPUT),
+    Day::new::<day20::Day20>(day20::INPUT),
+    Day::new::<day21::Day21>(day21::INPUT),
+    Day::new::<day22::Day22>(day22::INPUT),
+    Day::new::<day23::Day23>(day23::INPUT),
+    Day::new::<day24::Day24>(day24::INPUT),
+    Day::new::<day25::Day25>(day25::INPUT),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static PARSED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counting;
+
+    impl Solution for Counting {
+        type Input = usize;
+        type Part1 = usize;
+        type Part2 = usize;
+
+        const DAY: u8 = 1;
+
+        fn parse(input: &str) -> Result<usize> {
+            PARSED.fetch_add(1, Ordering::Relaxed);
+            input.parse().map_err(|_| Error::new("not a number"))
+        }
+
+        fn part1(n: &usize) -> Result<usize> {
+            Ok(n + 1)
+        }
+
+        fn part2(n: &usize) -> Result<usize> {
+            Ok(n + 2)
+        }
+    }
+
+    #[test]
+    fn parses_once() {
+        let answers = solve::<Counting>("40", &[Part::One, Part::Two]);
+        assert_eq!(PARSED.load(Ordering::Relaxed), 1);
+        let answers: Vec<_> = answers.into_iter().map(|a| (a.part, a.answer)).collect();
+        assert_eq!(
+            answers,
+            [
+                (Part::One, Ok("41".to_string())),
+                (Part::Two, Ok("42".to_string()))
+            ]
+        );
+        let answers = solve::<Counting>("x", &[Part::Two]);
+        assert_eq!(PARSED.load(Ordering::Relaxed), 2);
+        assert_eq!(answers[0].answer, Err(Error::new("not a number").on_day(1)));
+    }
+}
@@ -1,73 +1,301 @@
+use std::path::PathBuf;
+use std::time::Instant;
+
+use advent22::{Day, Part, DAYS};
 use rayon::prelude::*;
 
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day24;
-mod day25;
+mod bench;
+mod json;
+mod output;
+mod verify;
+
+use output::{Format, Record};
+
+/// How often bench runs each day, unless told otherwise.
+const DEFAULT_RUNS: usize = 10;
+
+const USAGE: &str = "\
+Usage: advent22 [run] [--day N] [--part 1|2] [--input PATH]
+                     [--format text|json|csv]
+       advent22 verify [--day N] [--part 1|2] [--record]
+       advent22 bench [--day N] [--input PATH] [--runs N]
+                      [--baseline PATH] [--save PATH]
+
+Runs the solutions for all days, or just the selected one. Inputs are
+embedded at compile time, but can be replaced at runtime using --input,
+which requires --day. --format json or csv prints a record for each
+part with its day, status, answer, and the time taken in nanoseconds.
+
+verify checks the answers for the embedded inputs against the ones
+recorded in answers/, and exits with status 1 on any mismatch. With
+--record, it records the current answers instead.
+
+bench runs the days one after another, 10 times each by default, and
+prints the min, median and 95th percentile of the time taken to parse
+and solve each part. --save writes those to a JSON file, which can be
+passed as --baseline to a later run to flag medians more than 10%
+slower as regressions, and exit with status 1.
+
+Set TIME to any non-empty value to print how long each day took.
+
+Parts that fail print an error in place of their answer, and the exit
+status is 1 if any part failed.";
 
 fn main() {
-    let start = std::time::Instant::now();
-    let mut outputs = [
-        day01::solve,
-        day02::solve,
-        day03::solve,
-        day04::solve,
-        day05::solve,
-        day06::solve,
-        day07::solve,
-        day08::solve,
-        day09::solve,
-        day10::solve,
-        day11::solve,
-        day12::solve,
-        day13::solve,
-        day14::solve,
-        day15::solve,
-        day16::solve,
-        day17::solve,
-        day18::solve,
-        day19::solve,
-        day20::solve,
-        day21::solve,
-        day22::solve,
-        day23::solve,
-        day24::solve,
-        day25::solve,
-    ]
-    .par_iter()
-    .enumerate()
-    .map(|(day, fun)| {
-        let day_start = std::time::Instant::now();
-        let mut output = format!("Day {}:\n{}", day + 1, fun());
-        if !std::env::var("TIME").unwrap_or_default().is_empty() {
-            output = format!("{output}\ntook {:?}", day_start.elapsed());
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{USAGE}");
+            return;
+        }
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    let days: Vec<usize> = match args.day {
+        Some(day) => vec![day],
+        None => (1..=DAYS.len()).collect(),
+    };
+    let parts = match args.part {
+        Some(part) => vec![part],
+        None => vec![Part::One, Part::Two],
+    };
+    let custom_input = match &args.input {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(input) => Some(input),
+            Err(e) => {
+                eprintln!("failed to read {}: {e}", path.display());
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let ok = match args.command {
+        Command::Run => run(&days, &parts, custom_input.as_deref(), args.format),
+        Command::Verify => verify::verify(&days, &parts, args.record),
+        Command::Bench => bench::bench(
+            &days,
+            custom_input.as_deref(),
+            args.runs.unwrap_or(DEFAULT_RUNS),
+            args.baseline.as_deref(),
+            args.save.as_deref(),
+        ),
+    };
+    if !ok {
+        std::process::exit(1);
+    }
+}
+
+/// Solves the selected days, printing the answers. Returns true if
+/// all parts succeeded.
+fn run(days: &[usize], parts: &[Part], custom_input: Option<&str>, format: Format) -> bool {
+    let timed = !std::env::var("TIME").unwrap_or_default().is_empty();
+
+    let start = Instant::now();
+    let records: Vec<Record> = days
+        .par_iter()
+        .flat_map_iter(|&day| {
+            let Day { solve, input, .. } = DAYS[day - 1];
+            let input = custom_input.unwrap_or(input);
+            solve(input, parts).into_iter().map(move |a| Record {
+                day,
+                part: a.part,
+                answer: a.answer.map_err(|e| e.to_string()),
+                duration: a.duration,
+            })
+        })
+        .collect();
+    let total = timed.then(|| start.elapsed());
+    print!("{}", format.write(&records, total));
+    records.iter().all(|r| r.answer.is_ok())
+}
+
+/// What the runner should do with the selected days.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+enum Command {
+    #[default]
+    Run,
+    Verify,
+    Bench,
+}
+
+/// Command line arguments for the runner.
+#[derive(Debug, Default, Eq, PartialEq)]
+struct Args {
+    command: Command,
+    day: Option<usize>,
+    part: Option<Part>,
+    input: Option<PathBuf>,
+    record: bool,
+    runs: Option<usize>,
+    baseline: Option<PathBuf>,
+    save: Option<PathBuf>,
+    format: Format,
+}
+
+impl Args {
+    /// Parses command line arguments, excluding the program name.
+    /// Returns None if usage information was requested.
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut rv = Self::default();
+        let mut args = args.into_iter().peekable();
+        // The subcommand is optional, and defaults to run.
+        match args.peek().map(String::as_str) {
+            Some("run") => {
+                args.next();
+            }
+            Some("verify") => {
+                rv.command = Command::Verify;
+                args.next();
+            }
+            Some("bench") => {
+                rv.command = Command::Bench;
+                args.next();
+            }
+            _ => {}
+        }
+        while let Some(arg) = args.next() {
+            let mut value = |flag: &str| args.next().ok_or(format!("missing value for {flag}"));
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-d" | "--day" => {
+                    let day = value(&arg)?;
+                    rv.day = match day.parse() {
+                        Ok(n) if (1..=DAYS.len()).contains(&n) => Some(n),
+                        _ => return Err(format!("invalid day: {day}")),
+                    };
+                }
+                "-p" | "--part" => {
+                    rv.part = match value(&arg)?.as_str() {
+                        "1" => Some(Part::One),
+                        "2" => Some(Part::Two),
+                        part => return Err(format!("invalid part: {part}")),
+                    };
+                }
+                "-i" | "--input" => rv.input = Some(value(&arg)?.into()),
+                "--record" => rv.record = true,
+                "--runs" => {
+                    let runs = value(&arg)?;
+                    rv.runs = match runs.parse() {
+                        Ok(n) if n > 0 => Some(n),
+                        _ => return Err(format!("invalid number of runs: {runs}")),
+                    };
+                }
+                "--baseline" => rv.baseline = Some(value(&arg)?.into()),
+                "--save" => rv.save = Some(value(&arg)?.into()),
+                "-f" | "--format" => {
+                    rv.format = match value(&arg)?.as_str() {
+                        "text" => Format::Text,
+                        "json" => Format::Json,
+                        "csv" => Format::Csv,
+                        format => return Err(format!("invalid format: {format}")),
+                    };
+                }
+                _ => return Err(format!("unexpected argument: {arg}")),
+            }
         }
-        output
-    })
-    .collect::<Vec<String>>();
-    if !std::env::var("TIME").unwrap_or_default().is_empty() {
-        outputs.push(format!("total: {:?}", start.elapsed()));
+        if rv.input.is_some() && rv.day.is_none() {
+            return Err("--input requires --day".to_string());
+        }
+        if rv.input.is_some() && rv.command == Command::Verify {
+            return Err("verify only uses the embedded inputs".to_string());
+        }
+        if rv.record && rv.command != Command::Verify {
+            return Err("--record requires verify".to_string());
+        }
+        if rv.command != Command::Bench
+            && (rv.runs.is_some() || rv.baseline.is_some() || rv.save.is_some())
+        {
+            return Err("--runs, --baseline and --save require bench".to_string());
+        }
+        if rv.format != Format::Text && rv.command != Command::Run {
+            return Err("--format requires run".to_string());
+        }
+        if rv.part.is_some() && rv.command == Command::Bench {
+            return Err("bench always times both parts".to_string());
+        }
+        Ok(Some(rv))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Option<Args>, String> {
+        Args::parse(args.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn parse_no_args() {
+        assert_eq!(parse(""), Ok(Some(Args::default())));
+    }
+
+    #[test]
+    fn parse_full_selection() {
+        assert_eq!(
+            parse("run --day 16 --part 2 --input path/to/file"),
+            Ok(Some(Args {
+                day: Some(16),
+                part: Some(Part::Two),
+                input: Some("path/to/file".into()),
+                ..Args::default()
+            }))
+        );
+    }
+
+    #[test]
+    fn parse_verify() {
+        assert_eq!(
+            parse("verify --day 10 --record"),
+            Ok(Some(Args {
+                command: Command::Verify,
+                day: Some(10),
+                record: true,
+                ..Args::default()
+            }))
+        );
+        assert!(parse("--record").is_err());
+        assert!(parse("verify --day 1 --input foo").is_err());
+    }
+
+    #[test]
+    fn parse_bench() {
+        assert_eq!(
+            parse("bench --runs 50 --baseline old.json --save new.json"),
+            Ok(Some(Args {
+                command: Command::Bench,
+                runs: Some(50),
+                baseline: Some("old.json".into()),
+                save: Some("new.json".into()),
+                ..Args::default()
+            }))
+        );
+        assert!(parse("bench --runs 0").is_err());
+        assert!(parse("bench --part 1").is_err());
+        assert!(parse("--save new.json").is_err());
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!(
+            parse("--format csv"),
+            Ok(Some(Args {
+                format: Format::Csv,
+                ..Args::default()
+            }))
+        );
+        assert!(parse("--format xml").is_err());
+        assert!(parse("verify --format json").is_err());
+    }
+
+    #[test]
+    fn parse_invalid_args() {
+        assert!(parse("--day 26").is_err());
+        assert!(parse("--part 3").is_err());
+        assert!(parse("--input foo").is_err());
+        assert!(parse("--day").is_err());
+        assert!(parse("walk").is_err());
     }
-    outputs.iter().for_each(|o| println!("{o}"));
 }
@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use advent22::Part;
+
+use crate::json::Value;
+
+/// The result of solving one part of a day.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Record {
+    pub day: usize,
+    pub part: Part,
+    /// The answer, or the error message if solving failed.
+    pub answer: Result<String, String>,
+    /// How long solving the part took, counting parsing for the
+    /// first part solved.
+    pub duration: Duration,
+}
+
+impl Record {
+    fn part(&self) -> usize {
+        match self.part {
+            Part::One => 1,
+            Part::Two => 2,
+        }
+    }
+
+    fn status(&self) -> &'static str {
+        match self.answer {
+            Ok(_) => "ok",
+            Err(_) => "error",
+        }
+    }
+
+    /// The record's fields, in order, as strings.
+    fn fields(&self) -> [String; 6] {
+        let (answer, error) = match &self.answer {
+            Ok(answer) => (answer.clone(), String::new()),
+            Err(e) => (String::new(), e.clone()),
+        };
+        [
+            self.day.to_string(),
+            self.part().to_string(),
+            self.status().to_string(),
+            answer,
+            self.duration.as_nanos().to_string(),
+            error,
+        ]
+    }
+}
+
+const FIELDS: [&str; 6] = ["day", "part", "status", "answer", "duration_ns", "error"];
+
+/// How the runner prints its results.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Format {
+    /// The answers for each day under a header, optionally with the
+    /// time each day took.
+    #[default]
+    Text,
+    /// An array with an object for each record.
+    Json,
+    /// A header, and a row for each record.
+    Csv,
+}
+
+impl Format {
+    /// Formats the records. Timings are only included in text if
+    /// `total` is given, and always in other formats.
+    pub fn write(self, records: &[Record], total: Option<Duration>) -> String {
+        match self {
+            Self::Text => text(records, total),
+            Self::Json => json(records),
+            Self::Csv => csv(records),
+        }
+    }
+}
+
+fn text(records: &[Record], total: Option<Duration>) -> String {
+    let mut rv = vec![];
+    for day in records.chunk_by(|a, b| a.day == b.day) {
+        rv.push(format!("Day {}:", day[0].day));
+        for record in day {
+            match &record.answer {
+                Ok(answer) => rv.push(answer.clone()),
+                Err(e) => rv.push(format!("error: {e}")),
+            }
+        }
+        if total.is_some() {
+            let took: Duration = day.iter().map(|r| r.duration).sum();
+            rv.push(format!("took {took:?}"));
+        }
+    }
+    if let Some(total) = total {
+        rv.push(format!("total: {total:?}"));
+    }
+    rv.join("\n") + "\n"
+}
+
+fn json(records: &[Record]) -> String {
+    let records = records
+        .iter()
+        .map(|r| {
+            let (answer, error) = match &r.answer {
+                Ok(answer) => (answer.as_str().into(), Value::Null),
+                Err(e) => (Value::Null, e.as_str().into()),
+            };
+            let values = [
+                r.day.into(),
+                r.part().into(),
+                r.status().into(),
+                answer,
+                (r.duration.as_nanos() as f64).into(),
+                error,
+            ];
+            Value::Object(FIELDS.map(String::from).into_iter().zip(values).collect())
+        })
+        .collect();
+    format!("{:#}\n", Value::Array(records))
+}
+
+fn csv(records: &[Record]) -> String {
+    let mut rv = FIELDS.join(",") + "\n";
+    for record in records {
+        let fields = record.fields().map(|f| csv_field(&f));
+        rv += &(fields.join(",") + "\n");
+    }
+    rv
+}
+
+/// Quotes a field if required, so that separators and line breaks
+/// within it are kept intact.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record {
+                day: 10,
+                part: Part::One,
+                answer: Ok("13140".to_string()),
+                duration: Duration::from_micros(5),
+            },
+            Record {
+                day: 10,
+                part: Part::Two,
+                answer: Ok("##..\n#..#\n".to_string()),
+                duration: Duration::from_micros(20),
+            },
+            Record {
+                day: 25,
+                part: Part::One,
+                answer: Err("day 25, line 2: invalid \"3\"".to_string()),
+                duration: Duration::from_nanos(800),
+            },
+        ]
+    }
+
+    #[test]
+    fn text_output() {
+        assert_eq!(
+            Format::Text.write(&records(), None),
+            "Day 10:\n13140\n##..\n#..#\n\nDay 25:\nerror: day 25, line 2: invalid \"3\"\n"
+        );
+        assert!(Format::Text
+            .write(&records(), Some(Duration::from_millis(1)))
+            .ends_with("Day 25:\nerror: day 25, line 2: invalid \"3\"\ntook 800ns\ntotal: 1ms\n"));
+    }
+
+    #[test]
+    fn json_output() {
+        let json = Value::parse(&Format::Json.write(&records(), None)).unwrap();
+        let records = json.as_array().unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records[1].get("answer"),
+            Some(&Value::String("##..\n#..#\n".to_string()))
+        );
+        assert_eq!(records[2].get("status"), Some(&Value::from("error")));
+        assert_eq!(records[2].get("answer"), Some(&Value::Null));
+        assert_eq!(records[0].get("duration_ns"), Some(&Value::Number(5000.0)));
+    }
+
+    #[test]
+    fn csv_output() {
+        assert_eq!(
+            Format::Csv.write(&records(), None),
+            "day,part,status,answer,duration_ns,error
+10,1,ok,13140,5000,
+10,2,ok,\"##..\n#..#\n\",20000,
+25,1,error,,800,\"day 25, line 2: invalid \"\"3\"\"\"
+"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use advent22::{Answer, Day, Part, DAYS};
+use rayon::prelude::*;
+
+/// The directory holding the recorded answers, one file per day.
+const ANSWERS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/answers");
+
+/// The recorded answers for a day. The answer file has part 1 on the
+/// first line, and part 2 on the remaining lines, as it may span
+/// several.
+#[derive(Debug, Default, Eq, PartialEq)]
+struct Answers {
+    part1: Option<String>,
+    part2: Option<String>,
+}
+
+impl Answers {
+    fn path(day: usize) -> PathBuf {
+        PathBuf::from(ANSWERS).join(format!("{day:02}.txt"))
+    }
+
+    /// Loads the answers for a day, which are empty if none were
+    /// recorded yet.
+    fn load(day: usize) -> std::io::Result<Self> {
+        match std::fs::read_to_string(Self::path(day)) {
+            Ok(s) => Ok(Self::parse(&s)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&self, day: usize) -> std::io::Result<()> {
+        std::fs::create_dir_all(ANSWERS)?;
+        std::fs::write(Self::path(day), self.to_string())
+    }
+
+    fn parse(s: &str) -> Self {
+        let (part1, part2) = s.split_once('\n').unwrap_or((s, ""));
+        let answer = |a: &str| Some(a.trim_end().to_string()).filter(|a| !a.is_empty());
+        Self {
+            part1: answer(part1),
+            part2: answer(part2),
+        }
+    }
+
+    fn get(&self, part: Part) -> Option<&str> {
+        match part {
+            Part::One => self.part1.as_deref(),
+            Part::Two => self.part2.as_deref(),
+        }
+    }
+
+    fn set(&mut self, part: Part, answer: &str) {
+        let answer = Some(answer.trim_end().to_string());
+        match part {
+            Part::One => self.part1 = answer,
+            Part::Two => self.part2 = answer,
+        }
+    }
+}
+
+impl fmt::Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.part1.as_deref().unwrap_or_default())?;
+        match &self.part2 {
+            Some(part2) => writeln!(f, "{part2}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The outcome of checking one part against its recorded answer.
+#[derive(Debug, Eq, PartialEq)]
+enum Status {
+    Pass,
+    Mismatch { expected: String, got: String },
+    Missing,
+    Recorded,
+    Failed(String),
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pass => write!(f, "pass"),
+            Self::Mismatch { expected, got } => {
+                write!(f, "FAIL, expected {expected:?} but got {got:?}")
+            }
+            Self::Missing => write!(f, "no recorded answer"),
+            Self::Recorded => write!(f, "recorded"),
+            Self::Failed(e) => write!(f, "error: {e}"),
+        }
+    }
+}
+
+/// Solves the selected days using their embedded inputs, and either
+/// checks the answers against the recorded ones, or records them.
+/// Prints a table of results, and returns true if all parts passed.
+pub fn verify(days: &[usize], parts: &[Part], record: bool) -> bool {
+    let results = days
+        .par_iter()
+        .map(|&day| {
+            let Day { solve, input, .. } = DAYS[day - 1];
+            let mut answers = match Answers::load(day) {
+                Ok(answers) => answers,
+                Err(e) => {
+                    let e = format!("failed to read {}: {e}", Answers::path(day).display());
+                    return vec![(day, Part::One, Status::Failed(e))];
+                }
+            };
+            let mut statuses = vec![];
+            for Answer { part, answer, .. } in solve(input, parts) {
+                let status = match answer {
+                    Ok(answer) if record => {
+                        answers.set(part, &answer);
+                        Status::Recorded
+                    }
+                    Ok(answer) => match answers.get(part) {
+                        Some(expected) if expected == answer.trim_end() => Status::Pass,
+                        Some(expected) => Status::Mismatch {
+                            expected: expected.to_string(),
+                            got: answer.trim_end().to_string(),
+                        },
+                        None => Status::Missing,
+                    },
+                    Err(e) => Status::Failed(e.to_string()),
+                };
+                statuses.push((day, part, status));
+            }
+            if record {
+                if let Err(e) = answers.save(day) {
+                    let e = format!("failed to write {}: {e}", Answers::path(day).display());
+                    statuses.push((day, parts[0], Status::Failed(e)));
+                }
+            }
+            statuses
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+
+    println!("day  part  result");
+    for (day, part, status) in &results {
+        let part = match part {
+            Part::One => 1,
+            Part::Two => 2,
+        };
+        println!("{day:>3}  {part:>4}  {status}");
+    }
+    let failures = results
+        .iter()
+        .filter(|(_, _, s)| !matches!(s, Status::Pass | Status::Recorded))
+        .count();
+    let succeeded = if record { "recorded" } else { "passed" };
+    println!(
+        "{} {succeeded}, {failures} failed",
+        results.len() - failures
+    );
+    failures == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_round_trip() {
+        let mut answers = Answers::parse("1741\n2316\n");
+        assert_eq!(answers.get(Part::One), Some("1741"));
+        assert_eq!(answers.get(Part::Two), Some("2316"));
+        answers.set(Part::Two, "#..#\n.##.\n");
+        assert_eq!(answers.to_string(), "1741\n#..#\n.##.\n");
+        assert_eq!(Answers::parse(&answers.to_string()), answers);
+    }
+
+    #[test]
+    fn answers_missing_parts() {
+        let answers = Answers::parse("2=-1=0\n");
+        assert_eq!(answers.get(Part::Two), None);
+        assert_eq!(answers.to_string(), "2=-1=0\n");
+        let answers = Answers::parse("\n42\n");
+        assert_eq!(answers.get(Part::One), None);
+        assert_eq!(Answers::parse(&answers.to_string()), answers);
+    }
+}